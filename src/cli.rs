@@ -0,0 +1,58 @@
+use serenity::all::GuildId;
+use std::io::{self, BufRead, Write};
+use tracing::Instrument;
+
+use crate::commands::invocation::CaptureSink;
+use crate::commands::{self, Invocation, Reply};
+use crate::permissions::PermissionLevel;
+
+/// Runs commands from the terminal instead of Discord and prints each reply to stdout.
+///
+/// Every line of input is handled like a message sent in `guild_id` (or a DM without one), e.g.
+/// `profile Faker#KR1 --region KR`. Permissions and cooldowns are not checked. With `input` the
+/// single command is run instead of reading lines from stdin.
+pub async fn run(guild_id: Option<GuildId>, input: Option<String>) -> anyhow::Result<()> {
+    if let Some(input) = input {
+        println!("{}", execute(guild_id, &input).await);
+        return Ok(());
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush()?;
+
+        let Some(line) = lines.next().transpose()? else {
+            println!();
+            return Ok(());
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if matches!(line, "exit" | "quit") {
+            return Ok(());
+        }
+
+        println!("{}", execute(guild_id, line).await);
+    }
+}
+
+async fn execute(guild_id: Option<GuildId>, line: &str) -> String {
+    let name = line.split_whitespace().next().unwrap_or_default();
+    let input = line[name.len()..].trim();
+
+    let commands = commands::all();
+    match commands.get(name) {
+        Some(command) => {
+            let span = tracing::info_span!("command", command = name, user = "cli", guild = guild_id.map(|id| id.get()));
+            let sink = CaptureSink::new();
+            // Whoever runs the CLI controls the bot anyway
+            let invocation = Invocation::new(guild_id, None, input, &sink).level(PermissionLevel::Owner);
+            command.run(&invocation).instrument(span).await;
+            sink.replies().iter().map(Reply::render_text).collect::<Vec<_>>().join("\n")
+        }
+        None => format!("Unknown command `{}`. Try `help`.", name),
+    }
+}
@@ -0,0 +1,287 @@
+use riven::consts::PlatformRoute;
+use serenity::all::{ButtonStyle, CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter};
+use serenity::async_trait;
+use std::time::Duration;
+
+use crate::commands::region::RegionCommand;
+use crate::commands::rotation;
+use crate::commands::{Command, ComponentId, ComponentPress, Invocation, Reply};
+use crate::ddragon::{self, models::Champion, StaticData};
+use crate::riot::region;
+use crate::utils;
+
+/// The overview, the passive and the four abilities.
+const PAGES: usize = 6;
+const ABILITY_KEYS: [&str; 4] = ["Q", "W", "E", "R"];
+
+/// Base stats shown on the overview, as (label, Data Dragon stat, per-level stat).
+const STATS: &[(&str, &str, &str)] = &[
+    ("Health", "hp", "hpperlevel"),
+    ("Health regen", "hpregen", "hpregenperlevel"),
+    ("Resource", "mp", "mpperlevel"),
+    ("Resource regen", "mpregen", "mpregenperlevel"),
+    ("Attack damage", "attackdamage", "attackdamageperlevel"),
+    ("Attack speed", "attackspeed", "attackspeedperlevel"),
+    ("Armor", "armor", "armorperlevel"),
+    ("Magic resist", "spellblock", "spellblockperlevel"),
+    ("Move speed", "movespeed", ""),
+    ("Attack range", "attackrange", ""),
+];
+
+pub struct ChampionCommand;
+
+impl ChampionCommand {
+    pub fn new() -> Self {
+        ChampionCommand
+    }
+}
+
+/// Whether the champion is in the free rotation of the region it was looked up in. Kept in the
+/// buttons' state, so paging does not ask Riot again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rotation {
+    Free(PlatformRoute),
+    NotFree(PlatformRoute),
+    Unknown,
+}
+
+impl Rotation {
+    fn encode(self) -> String {
+        match self {
+            Rotation::Free(platform) => format!("f{}", platform),
+            Rotation::NotFree(platform) => format!("n{}", platform),
+            Rotation::Unknown => "u".to_string(),
+        }
+    }
+
+    fn parse(state: &str) -> Self {
+        let platform = || state.get(1..).and_then(|code| code.parse().ok());
+        match (state.chars().next(), platform()) {
+            (Some('f'), Some(platform)) => Rotation::Free(platform),
+            (Some('n'), Some(platform)) => Rotation::NotFree(platform),
+            _ => Rotation::Unknown,
+        }
+    }
+
+    fn describe(self) -> String {
+        match self {
+            Rotation::Free(platform) => format!("✅ Free to play this week on {}", region::label(platform)),
+            Rotation::NotFree(platform) => format!("Not in this week's free rotation on {}", region::label(platform)),
+            Rotation::Unknown => "Could not load this week's free rotation".to_string(),
+        }
+    }
+}
+
+/// The button state: `<champion key>:<page>:<rotation>`.
+fn encode_state(champion: &Champion, page: usize, rotation: Rotation) -> String {
+    format!("{}:{}:{}", champion.key, page, rotation.encode())
+}
+
+fn parse_state(state: &str) -> Option<(i64, usize, Rotation)> {
+    let mut parts = state.splitn(3, ':');
+    let key = parts.next()?.parse().ok()?;
+    let page = parts.next()?.parse().ok()?;
+    Some((key, page, Rotation::parse(parts.next()?)))
+}
+
+#[async_trait]
+impl Command for ChampionCommand {
+    async fn run(&self, invocation: &Invocation<'_>) {
+        let (input, platform) = match RegionCommand::resolve(invocation.guild_id, invocation.input) {
+            Ok(resolved) => resolved,
+            Err(reply) => {
+                invocation.send(reply).await;
+                return;
+            }
+        };
+        if input.trim().is_empty() {
+            invocation.send("Usage: `champion <name>`, e.g. `champion ahri`.").await;
+            return;
+        }
+
+        invocation.defer(false).await;
+
+        let data = match ddragon::get().await {
+            Ok(data) => data,
+            Err(why) => {
+                tracing::warn!("Error loading Data Dragon: {:#}", why);
+                invocation.send("Champion data is not available right now, try again later.").await;
+                return;
+            }
+        };
+
+        let champion = match data.find_champion(&input) {
+            Ok(champion) => champion,
+            Err(why) => {
+                invocation.send(why.to_string()).await;
+                return;
+            }
+        };
+
+        let key: i16 = champion.key.parse().unwrap_or_default();
+        let rotation = match rotation::fetch(platform).await {
+            Ok(info) if info.free_champion_ids.iter().any(|free| free.0 == key) => Rotation::Free(platform),
+            Ok(_) => Rotation::NotFree(platform),
+            Err(why) => {
+                tracing::warn!("Error fetching the free rotation: {:#}", why);
+                Rotation::Unknown
+            }
+        };
+
+        let id = ComponentId::new(self.name(), "", encode_state(champion, 0, rotation));
+        invocation.send(render(&data, champion, 0, rotation, &id)).await;
+    }
+
+    async fn handle_component(&self, invocation: &Invocation<'_>, press: &ComponentPress) {
+        let data = ddragon::get().await;
+        let found = parse_state(&press.id.state).and_then(|(key, page, rotation)| {
+            let data = data.as_ref().ok()?;
+            Some((data.champion_by_key(key)?, page, rotation))
+        });
+        let (Ok(data), Some((champion, page, rotation))) = (data.as_ref(), found) else {
+            tracing::debug!(state = %press.id.state, "Champion page state is no longer valid");
+            invocation.send(Reply::text("This menu is no longer active.").ephemeral(true)).await;
+            return;
+        };
+
+        let page = match press.id.action.as_str() {
+            "previous" => page.saturating_sub(1),
+            "next" => (page + 1).min(PAGES - 1),
+            _ => 0,
+        };
+        // Keep the expiry so the buttons still expire with the original message
+        let id = press.id.with_state(encode_state(champion, page, rotation));
+        invocation.edit(render(data, champion, page, rotation, &id)).await;
+    }
+
+    fn autocomplete(&self, input: &str) -> Vec<String> {
+        ddragon::cached().map(|data| data.suggest_champions(input, 25)).unwrap_or_default()
+    }
+
+    fn autocompletes(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str {
+        "champion"
+    }
+
+    fn description(&self) -> &str {
+        "Show a champion's roles, abilities and base stats."
+    }
+
+    fn takes_region(&self) -> bool {
+        true
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+}
+
+fn render(data: &StaticData, champion: &Champion, page: usize, rotation: Rotation, id: &ComponentId) -> Reply {
+    let embed = match page {
+        0 => overview(data, champion, rotation),
+        1 => passive(data, champion),
+        _ => ability(data, champion, page - 2),
+    };
+    let embed = embed.footer(CreateEmbedFooter::new(format!("Page {}/{} · Patch {}", page + 1, PAGES, data.version)));
+
+    let button = |action: &str, label: &str, disabled: bool| {
+        CreateButton::new(ComponentId { action: action.to_string(), ..id.clone() }.encode())
+            .label(label)
+            .style(ButtonStyle::Secondary)
+            .disabled(disabled)
+    };
+    let buttons = CreateActionRow::Buttons(vec![
+        button("previous", "◀ Previous", page == 0),
+        button("overview", "Overview", page == 0),
+        button("next", "Next ▶", page + 1 >= PAGES),
+    ]);
+
+    Reply::default().embed(embed).components(vec![buttons])
+}
+
+fn overview(data: &StaticData, champion: &Champion, rotation: Rotation) -> CreateEmbed {
+    let stat = |name: &str| champion.stats.get(name).copied().unwrap_or_default();
+
+    let mut stats = format!("{: <15} {: >7} {: >9} {: >7}\n", "", "Level 1", "Per level", "Level 18");
+    for (label, base, growth) in STATS {
+        let label = if *label == "Resource" || *label == "Resource regen" {
+            label.replace("Resource", resource_name(champion))
+        } else {
+            label.to_string()
+        };
+        if *base == "attackspeed" {
+            // Attack speed grows by a percentage of the base value
+            let level_18 = stat(base) * (1.0 + stat(growth) * 17.0 / 100.0);
+            stats.push_str(&format!("{: <15} {: >7.3} {: >8}% {: >8.3}\n", label, stat(base), stat(growth), level_18));
+        } else if growth.is_empty() {
+            stats.push_str(&format!("{: <15} {: >7}\n", label, stat(base)));
+        } else {
+            // Growth is spread unevenly over the levels but always adds up to 17 times the per-level value
+            let level_18 = stat(base) + stat(growth) * 17.0;
+            stats.push_str(&format!("{: <15} {: >7} {: >9} {: >8}\n", label, stat(base), stat(growth), round(level_18)));
+        }
+    }
+
+    let abilities: Vec<String> = std::iter::once(format!("**Passive**: {}", champion.passive.name))
+        .chain(champion.spells.iter().zip(ABILITY_KEYS).map(|(spell, key)| format!("**{}**: {}", key, spell.name)))
+        .collect();
+
+    CreateEmbed::new()
+        .title(format!("{}, {}", champion.name, champion.title))
+        .thumbnail(data.champion_icon_url(champion))
+        .description(format!("{} · {}", champion.tags.join(", "), resource_name(champion)))
+        .field("Free rotation", rotation.describe(), false)
+        .field("Abilities", abilities.join("\n"), false)
+        .field("Base stats", format!("```\n{}```", stats), false)
+}
+
+fn passive(data: &StaticData, champion: &Champion) -> CreateEmbed {
+    CreateEmbed::new()
+        .title(format!("{} · Passive: {}", champion.name, champion.passive.name))
+        .thumbnail(data.passive_icon_url(&champion.passive.image))
+        .description(utils::truncate(&ddragon::plain_text(&champion.passive.description), 4000))
+}
+
+fn ability(data: &StaticData, champion: &Champion, index: usize) -> CreateEmbed {
+    let (Some(spell), Some(key)) = (champion.spells.get(index), ABILITY_KEYS.get(index)) else {
+        return CreateEmbed::new().title(champion.name.clone()).description("This ability is missing from Data Dragon.");
+    };
+
+    let cost = spell
+        .resource
+        .replace("{{ cost }}", &spell.cost_burn)
+        .replace("{{ abilityresourcename }}", resource_name(champion));
+    let cooldown = match spell.cooldown_burn.as_str() {
+        "" | "0" => "None".to_string(),
+        cooldown => format!("{}s", cooldown),
+    };
+
+    let mut embed = CreateEmbed::new()
+        .title(format!("{} · {}: {}", champion.name, key, spell.name))
+        .thumbnail(data.spell_icon_url(&spell.image))
+        .description(utils::truncate(&ddragon::plain_text(&spell.description), 4000))
+        .field("Cooldown", cooldown, true);
+    // Placeholders that are left over mean Data Dragon has no numbers for this cost
+    if !cost.is_empty() && !cost.contains("{{") {
+        embed = embed.field("Cost", cost, true);
+    }
+    // Abilities without a meaningful range list a huge number
+    if !spell.range_burn.is_empty() && spell.range_burn != "25000" {
+        embed = embed.field("Range", spell.range_burn.clone(), true);
+    }
+    embed.field("Ranks", spell.maxrank.to_string(), true)
+}
+
+fn resource_name(champion: &Champion) -> &str {
+    match champion.partype.as_str() {
+        "" | "None" => "No resource",
+        partype => partype,
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
@@ -0,0 +1,443 @@
+use anyhow::Result;
+use riven::consts::{Champion, PlatformRoute};
+use riven::models::match_v5::{Match, Participant};
+use serenity::all::{CreateEmbed, CreateEmbedFooter};
+use serenity::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::commands::region::RegionCommand;
+use crate::commands::{Command, Invocation, Reply};
+use crate::{ddragon, utils};
+use crate::riot::matches::{self, Kind};
+use crate::riot::player;
+use crate::riot::{self, region};
+
+const USAGE: &str = "Usage: `champstats <name#tag> [champion] [games] [--sort games|winrate]`, \
+    e.g. `champstats Faker#KR1` or `champstats Faker#KR1 ahri 50`";
+const DEFAULT_GAMES: i32 = 30;
+const MAX_CHAMPIONS: usize = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sort {
+    Games,
+    WinRate,
+}
+
+impl Sort {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "games" | "played" => Some(Sort::Games),
+            "winrate" | "wr" | "wins" => Some(Sort::WinRate),
+            _ => None,
+        }
+    }
+}
+
+pub struct ChampStatsCommand;
+
+impl ChampStatsCommand {
+    pub fn new() -> Self {
+        ChampStatsCommand
+    }
+}
+
+#[async_trait]
+impl Command for ChampStatsCommand {
+    async fn run(&self, invocation: &Invocation<'_>) {
+        let (input, platform) = match RegionCommand::resolve(invocation.guild_id, invocation.input) {
+            Ok(resolved) => resolved,
+            Err(reply) => {
+                invocation.send(reply).await;
+                return;
+            }
+        };
+        let Some((input, sort)) = take_sort(&input) else {
+            invocation.send(USAGE).await;
+            return;
+        };
+
+        let mut words: Vec<&str> = input.split_whitespace().collect();
+        let count = match words.last().and_then(|last| last.parse::<i32>().ok()) {
+            Some(count) => {
+                words.pop();
+                count.clamp(1, matches::MAX_COUNT)
+            }
+            None => DEFAULT_GAMES,
+        };
+        let input = words.join(" ");
+        let (riot_id, champion) = match player::split_riot_ids(&input)[..] {
+            [riot_id] => (riot_id.to_string(), None),
+            [riot_id, champion] => (riot_id.to_string(), Some(champion.to_string())),
+            _ => {
+                invocation.send(USAGE).await;
+                return;
+            }
+        };
+
+        invocation.defer(false).await;
+
+        let data = match ddragon::get().await {
+            Ok(data) => Some(data),
+            Err(why) => {
+                tracing::warn!("Error loading Data Dragon: {:#}", why);
+                None
+            }
+        };
+        let champion = match (champion, &data) {
+            (None, _) => None,
+            (Some(champion), Some(data)) => match data.find_champion(&champion) {
+                Ok(found) => match found.key.parse() {
+                    Ok(key) => Some((Champion(key), found.name.clone())),
+                    Err(why) => {
+                        tracing::warn!("Data Dragon key `{}` of {} is not a number: {}", found.key, found.name, why);
+                        invocation.send(format!("Champion data for {} is broken, try again later.", found.name)).await;
+                        return;
+                    }
+                },
+                Err(why) => {
+                    invocation.send(why.to_string()).await;
+                    return;
+                }
+            },
+            (Some(_), None) => {
+                invocation.send("Champion data is not available right now, try again later.").await;
+                return;
+            }
+        };
+
+        let response = match run_champstats(platform, &riot_id, champion, count, sort).await {
+            Ok(response) => response,
+            Err(why) => {
+                tracing::warn!("Error fetching champion statistics: {:#}", why);
+                riot::error_reply(&why, "Failed to fetch champion statistics.").into()
+            }
+        };
+        invocation.send(response).await;
+    }
+
+    /// Completes the champion after the Riot ID.
+    fn autocomplete(&self, input: &str) -> Vec<String> {
+        let Some(hash) = input.find('#') else { return Vec::new() };
+        let Some(space) = input[hash..].find(' ').map(|space| hash + space) else { return Vec::new() };
+        let (riot_id, champion) = (&input[..space], input[space..].trim());
+        ddragon::cached()
+            .map(|data| data.suggest_champions(champion, 25))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|name| format!("{} {}", riot_id, name))
+            .collect()
+    }
+
+    fn autocompletes(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str {
+        "champstats"
+    }
+
+    fn description(&self) -> &str {
+        "Show how a player does on each champion in their recent ranked games."
+    }
+
+    fn takes_region(&self) -> bool {
+        true
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(15)
+    }
+}
+
+/// Splits a `--sort <order>` or `sort:<order>` option off the input. `None` if the order is unknown.
+fn take_sort(input: &str) -> Option<(String, Sort)> {
+    let mut rest = Vec::new();
+    let mut sort = Sort::Games;
+
+    let mut words = input.split_whitespace();
+    while let Some(word) = words.next() {
+        let value = if word.eq_ignore_ascii_case("--sort") {
+            words.next().unwrap_or_default()
+        } else if word.get(..5).is_some_and(|head| head.eq_ignore_ascii_case("sort:")) {
+            match &word[5..] {
+                "" => words.next().unwrap_or_default(),
+                value => value,
+            }
+        } else {
+            rest.push(word);
+            continue;
+        };
+
+        sort = Sort::parse(value)?;
+    }
+
+    Some((rest.join(" "), sort))
+}
+
+/// One champion's totals over the games it was played in.
+#[derive(Default)]
+struct ChampionStats {
+    games: u32,
+    wins: u32,
+    kills: i32,
+    deaths: i32,
+    assists: i32,
+    minions: i32,
+    minutes: f64,
+    damage: i64,
+    team_damage: i64,
+    team_kills: i32,
+    vision: i32,
+}
+
+impl ChampionStats {
+    fn add(&mut self, game: &Match, own: &Participant) {
+        let team = game.info.participants.iter().filter(|participant| participant.team_id == own.team_id);
+
+        self.games += 1;
+        self.wins += own.win as u32;
+        self.kills += own.kills;
+        self.deaths += own.deaths;
+        self.assists += own.assists;
+        self.minions += own.total_minions_killed + own.neutral_minions_killed;
+        self.minutes += game.info.game_duration as f64 / 60.0;
+        self.damage += own.total_damage_dealt_to_champions as i64;
+        for teammate in team {
+            self.team_damage += teammate.total_damage_dealt_to_champions as i64;
+            self.team_kills += teammate.kills;
+        }
+        self.vision += own.vision_score;
+    }
+
+    fn win_rate(&self) -> f64 {
+        player::win_rate(self.wins, self.games - self.wins)
+    }
+
+    fn average(&self, total: i32) -> f64 {
+        total as f64 / self.games.max(1) as f64
+    }
+
+    /// Kills plus assists per death, with deathless games counting as one death.
+    fn kda(&self) -> f64 {
+        (self.kills + self.assists) as f64 / self.deaths.max(1) as f64
+    }
+
+    fn cs_per_minute(&self) -> f64 {
+        if self.minutes > 0.0 { self.minions as f64 / self.minutes } else { 0.0 }
+    }
+
+    fn damage_share(&self) -> f64 {
+        percent(self.damage as f64, self.team_damage as f64)
+    }
+
+    fn kill_participation(&self) -> f64 {
+        percent((self.kills + self.assists) as f64, self.team_kills as f64)
+    }
+}
+
+fn percent(part: f64, whole: f64) -> f64 {
+    if whole > 0.0 { part / whole * 100.0 } else { 0.0 }
+}
+
+/// Per champion totals, in the order the champions were first seen.
+fn aggregate(games: &[Arc<Match>], puuid: &str) -> Vec<(Champion, ChampionStats)> {
+    let mut champions: Vec<(Champion, ChampionStats)> = Vec::new();
+    for game in games {
+        let Some(own) = matches::participant(game, puuid) else { continue };
+        let Ok(champion) = own.champion() else { continue };
+        let index = match champions.iter().position(|(known, _)| *known == champion) {
+            Some(index) => index,
+            None => {
+                champions.push((champion, ChampionStats::default()));
+                champions.len() - 1
+            }
+        };
+        champions[index].1.add(game, own);
+    }
+    champions
+}
+
+async fn run_champstats(
+    platform: PlatformRoute,
+    riot_id: &str,
+    champion: Option<(Champion, String)>,
+    count: i32,
+    sort: Sort,
+) -> Result<Reply> {
+    let riot_api = riot::api()?;
+    let (game_name, tag_line) = player::parse_riot_id(riot_id);
+    let Some(account) = player::account(riot_api, platform, game_name, tag_line).await? else {
+        return Ok(format!("There is no summoner named `{}`.", riot_id).into());
+    };
+    let name = player::riot_id(&account);
+
+    let history = matches::recent(riot_api, platform, &account.puuid, Kind::Ranked, count).await?;
+    let games = &history.games;
+    let mut champions = aggregate(games, &account.puuid);
+    let note = history.skipped_note().map(|note| format!("\n_{}_", note)).unwrap_or_default();
+    let scope = format!("{}\nLast {} ranked games{}", region::label(platform), games.len(), note);
+
+    if let Some((champion, champion_name)) = champion {
+        let Some((_, stats)) = champions.iter().find(|(played, _)| *played == champion) else {
+            return Ok(format!("{} did not play {} in their last {} ranked games.{}", name, champion_name, games.len(), note).into());
+        };
+        let embed = CreateEmbed::new()
+            .title(format!("{} on {}", name, champion_name))
+            .description(scope)
+            .field("Games", format!("{} ({}W {}L)", stats.games, stats.wins, stats.games - stats.wins), true)
+            .field("Win rate", format!("{}%", stats.win_rate()), true)
+            .field(
+                "KDA",
+                format!(
+                    "{:.1}/{:.1}/{:.1} ({:.2})",
+                    stats.average(stats.kills),
+                    stats.average(stats.deaths),
+                    stats.average(stats.assists),
+                    stats.kda()
+                ),
+                true,
+            )
+            .field("CS/min", format!("{:.1}", stats.cs_per_minute()), true)
+            .field("Damage share", format!("{:.0}%", stats.damage_share()), true)
+            .field("Kill participation", format!("{:.0}%", stats.kill_participation()), true)
+            .field("Vision score", format!("{:.1}", stats.average(stats.vision)), true);
+        return Ok(Reply::default().embed(embed));
+    }
+
+    if champions.is_empty() {
+        return Ok(format!("{} has no ranked games in their match history.", name).into());
+    }
+    match sort {
+        Sort::Games => champions.sort_by(|a, b| b.1.games.cmp(&a.1.games).then(b.1.wins.cmp(&a.1.wins))),
+        Sort::WinRate => champions.sort_by(|a, b| {
+            b.1.win_rate().total_cmp(&a.1.win_rate()).then(b.1.games.cmp(&a.1.games))
+        }),
+    }
+
+    let mut table = format!(
+        "{: <13}{: >3}{: >5}{: >6}{: >6}{: >5}{: >5}{: >5}\n",
+        "Champion", "G", "WR", "KDA", "CS/m", "DMG", "KP", "VS"
+    );
+    for (champion, stats) in champions.iter().take(MAX_CHAMPIONS) {
+        table.push_str(&format!(
+            "{: <13}{: >3}{: >4}%{: >6.2}{: >6.1}{: >4.0}%{: >4.0}%{: >5.0}\n",
+            utils::truncate(&ddragon::champion_name(*champion), 12),
+            stats.games,
+            stats.win_rate(),
+            stats.kda(),
+            stats.cs_per_minute(),
+            stats.damage_share(),
+            stats.kill_participation(),
+            stats.average(stats.vision),
+        ));
+    }
+    let mut description = format!("{}\n```\n{}```", scope, table);
+    if champions.len() > MAX_CHAMPIONS {
+        description.push_str(&format!("{} more champions not shown", champions.len() - MAX_CHAMPIONS));
+    }
+    let sorted_by = match sort {
+        Sort::Games => "games played",
+        Sort::WinRate => "win rate",
+    };
+
+    let embed = CreateEmbed::new()
+        .title(format!("Champion statistics for {}", name))
+        .description(description)
+        .footer(CreateEmbedFooter::new(format!(
+            "Sorted by {}. KDA is (kills + assists) / deaths, DMG the share of team damage to champions, KP kill participation, VS vision score.",
+            sorted_by
+        )));
+    Ok(Reply::default().embed(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn game(id: &str) -> Match {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/asia/lol/match/v5/matches").join(id);
+        let path = path.with_extension("json");
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn takes_the_sort_option_anywhere() {
+        assert_eq!(take_sort("Faker#KR1 ahri"), Some(("Faker#KR1 ahri".to_string(), Sort::Games)));
+        assert_eq!(take_sort("Faker#KR1 --sort winrate 50"), Some(("Faker#KR1 50".to_string(), Sort::WinRate)));
+        assert_eq!(take_sort("--SORT wr Faker#KR1"), Some(("Faker#KR1".to_string(), Sort::WinRate)));
+        assert_eq!(take_sort("Faker#KR1 sort:played"), Some(("Faker#KR1".to_string(), Sort::Games)));
+        assert_eq!(take_sort("Faker#KR1 Sort: wins"), Some(("Faker#KR1".to_string(), Sort::WinRate)));
+    }
+
+    #[test]
+    fn rejects_unknown_sort_orders() {
+        assert_eq!(take_sort("Faker#KR1 --sort kda"), None);
+        assert_eq!(take_sort("Faker#KR1 --sort"), None);
+        assert_eq!(take_sort("Faker#KR1 sort:"), None);
+    }
+
+    #[test]
+    fn totals_games_per_champion() {
+        let first = game("KR_7000000001");
+        let mut deathless = first.clone();
+        let own = deathless.info.participants.iter_mut().find(|participant| participant.puuid == "fixture-puuid-faker");
+        own.unwrap().deaths = 0;
+        let games: Vec<Arc<Match>> = [first, game("KR_7000000004"), deathless].into_iter().map(Arc::new).collect();
+
+        let champions = aggregate(&games, "fixture-puuid-faker");
+        let played: Vec<_> = champions.iter().map(|(champion, stats)| (*champion, stats.games, stats.wins)).collect();
+        assert_eq!(played, [(Champion(103), 2, 2), (Champion(7), 1, 1)]);
+
+        // 9/3/7 and 9/0/7 with 184 CS over 30 minutes each, in a team with 18 kills and 111000 damage
+        let stats = &champions[0].1;
+        assert_eq!((stats.kills, stats.deaths, stats.assists), (18, 3, 14));
+        assert!((stats.kda() - 32.0 / 3.0).abs() < 1e-9);
+        assert!((stats.cs_per_minute() - 368.0 / 60.0).abs() < 1e-9);
+        assert!((stats.damage_share() - 66000.0 / 222000.0 * 100.0).abs() < 1e-9);
+        assert!((stats.kill_participation() - 32.0 / 36.0 * 100.0).abs() < 1e-9);
+        assert_eq!(stats.average(stats.vision), 25.0);
+    }
+
+    #[test]
+    fn deathless_games_count_as_one_death() {
+        let mut stats = ChampionStats::default();
+        let mut deathless = game("KR_7000000001");
+        let own = deathless.info.participants.iter_mut().find(|participant| participant.puuid == "fixture-puuid-faker");
+        own.unwrap().deaths = 0;
+        stats.add(&deathless, matches::participant(&deathless, "fixture-puuid-faker").unwrap());
+
+        assert_eq!(stats.kda(), 16.0);
+        assert_eq!(stats.win_rate(), 100.0);
+    }
+
+    #[test]
+    fn empty_totals_do_not_divide_by_zero() {
+        let stats = ChampionStats::default();
+        assert_eq!(
+            (stats.kda(), stats.cs_per_minute(), stats.damage_share(), stats.kill_participation()),
+            (0.0, 0.0, 0.0, 0.0)
+        );
+        assert_eq!(stats.average(stats.kills), 0.0);
+    }
+
+    #[test]
+    fn autocompletes_the_champion_after_the_riot_id() {
+        ddragon::use_test_fixtures();
+        let command = ChampStatsCommand::new();
+
+        assert_eq!(command.autocomplete("Hide on bush#KR1 or")[0], "Hide on bush#KR1 Orianna");
+        assert_eq!(command.autocomplete("Faker#KR1 tf")[0], "Faker#KR1 Twisted Fate");
+        assert_eq!(command.autocomplete("Faker#KR1 ").len(), 6);
+    }
+
+    #[test]
+    fn waits_for_a_complete_riot_id() {
+        ddragon::use_test_fixtures();
+        let command = ChampStatsCommand::new();
+
+        assert!(command.autocomplete("Faker").is_empty());
+        assert!(command.autocomplete("Faker#KR").is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+use anyhow::Result;
+use riven::consts::{PlatformRoute, QueueType};
+use riven::models::account_v1::Account;
+use riven::models::champion_mastery_v4::ChampionMastery;
+use serenity::all::CreateEmbed;
+use serenity::async_trait;
+use std::time::Duration;
+
+use crate::commands::region::RegionCommand;
+use crate::commands::{Command, Invocation, Reply};
+use crate::ddragon;
+use crate::riot::player::{self, RecentForm, Summary};
+use crate::riot::{self, region};
+
+const USAGE: &str = "Usage: `compare <name#tag> <name#tag>`, e.g. `compare Faker#KR1 Chovy#KR1`";
+const RECENT_MATCHES: i32 = 10;
+const TOP_CHAMPIONS: usize = 3;
+const SHARED_CHAMPIONS: usize = 5;
+
+pub struct CompareCommand;
+
+impl CompareCommand {
+    pub fn new() -> Self {
+        CompareCommand
+    }
+}
+
+#[async_trait]
+impl Command for CompareCommand {
+    async fn run(&self, invocation: &Invocation<'_>) {
+        let (input, platform) = match RegionCommand::resolve(invocation.guild_id, invocation.input) {
+            Ok(resolved) => resolved,
+            Err(reply) => {
+                invocation.send(reply).await;
+                return;
+            }
+        };
+
+        let [first, second] = player::split_riot_ids(&input)[..] else {
+            invocation.send(USAGE).await;
+            return;
+        };
+
+        invocation.defer(false).await;
+
+        if let Err(why) = ddragon::get().await {
+            tracing::warn!("Error loading Data Dragon: {:#}", why);
+        }
+
+        let (first, second) = futures::join!(fetch(platform, first), fetch(platform, second));
+        let response = match (first, second) {
+            (Ok(first), Ok(second)) => Reply::default().embed(render(platform, &first, &second)),
+            (Err(why), _) | (_, Err(why)) => {
+                tracing::warn!("Error comparing players: {:#}", why);
+                match why.downcast_ref::<UnknownPlayer>() {
+                    Some(unknown) => unknown.to_string().into(),
+                    None => riot::error_reply(&why, "Failed to compare the players.").into(),
+                }
+            }
+        };
+        invocation.send(response).await;
+    }
+
+    fn name(&self) -> &str {
+        "compare"
+    }
+
+    fn description(&self) -> &str {
+        "Compare the ranks, champions and recent form of two players."
+    }
+
+    fn takes_region(&self) -> bool {
+        true
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(15)
+    }
+}
+
+#[derive(Debug)]
+struct UnknownPlayer(String);
+
+impl std::fmt::Display for UnknownPlayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "There is no summoner named `{}`.", self.0)
+    }
+}
+
+impl std::error::Error for UnknownPlayer {}
+
+/// One side of the comparison. Sections that fail are shown as unavailable instead of failing the
+/// whole command.
+struct Player {
+    account: Account,
+    summary: Result<Summary>,
+    masteries: Result<Vec<ChampionMastery>>,
+    recent: Result<Option<RecentForm>>,
+}
+
+async fn fetch(platform: PlatformRoute, riot_id: &str) -> Result<Player> {
+    let riot_api = riot::api()?;
+    let (game_name, tag_line) = player::parse_riot_id(riot_id);
+    let Some(account) = player::account(riot_api, platform, game_name, tag_line).await? else {
+        return Err(UnknownPlayer(riot_id.to_string()).into());
+    };
+
+    let (summary, masteries, recent) = futures::join!(
+        player::summary(riot_api, platform, &account.puuid),
+        player::masteries(riot_api, platform, &account.puuid),
+        player::recent_form(riot_api, platform, &account.puuid, RECENT_MATCHES),
+    );
+    for (section, error) in [
+        ("summoner statistics", summary.as_ref().err()),
+        ("champion masteries", masteries.as_ref().err()),
+        ("recent matches", recent.as_ref().err()),
+    ] {
+        if let Some(why) = error {
+            tracing::warn!("Error fetching {} for {}: {:#}", section, riot_id, why);
+        }
+    }
+
+    Ok(Player { account, summary, masteries, recent })
+}
+
+fn render(platform: PlatformRoute, first: &Player, second: &Player) -> CreateEmbed {
+    let first_id = player::riot_id(&first.account);
+    let second_id = player::riot_id(&second.account);
+
+    CreateEmbed::new()
+        .title(format!("{} vs {}", first_id, second_id))
+        .description(region::label(platform))
+        .field(first_id, describe(first), true)
+        .field(second_id, describe(second), true)
+        .field("Shared champions", shared_champions(first, second), false)
+}
+
+fn describe(side: &Player) -> String {
+    let mut lines = Vec::new();
+
+    match &side.summary {
+        Ok(summary) => {
+            lines.push(format!("**Level** {}", summary.level));
+            for queue in [QueueType::RANKED_SOLO_5x5, QueueType::RANKED_FLEX_SR] {
+                match summary.rank(&queue) {
+                    Some(rank) => lines.push(format!(
+                        "**{}** {}\n{}W {}L ({}%)",
+                        rank.queue_name(),
+                        rank.rank,
+                        rank.wins,
+                        rank.losses,
+                        rank.win_rate()
+                    )),
+                    None => lines.push(format!("**{}** Unranked", player::queue_name(&queue))),
+                }
+            }
+        }
+        Err(_) => lines.push("_Ranks unavailable_".to_string()),
+    }
+
+    match &side.masteries {
+        Ok(masteries) if !masteries.is_empty() => {
+            let top: Vec<String> = masteries
+                .iter()
+                .take(TOP_CHAMPIONS)
+                .map(|mastery| format!("{} ({})", ddragon::champion_name(mastery.champion_id), mastery.champion_points))
+                .collect();
+            lines.push(format!("**Top champions**\n{}", top.join("\n")));
+        }
+        Ok(_) => lines.push("**Top champions** None".to_string()),
+        Err(_) => lines.push("_Champion masteries unavailable_".to_string()),
+    }
+
+    match &side.recent {
+        Ok(Some(form)) => {
+            lines.push(format!(
+                "**Last {} games** {}W {}L\n{:.1}/{:.1}/{:.1} KDA",
+                form.games,
+                form.wins,
+                form.games - form.wins,
+                form.kills,
+                form.deaths,
+                form.assists
+            ));
+            if let Some(note) = &form.skipped_note {
+                lines.push(format!("_{}_", note));
+            }
+            if !form.roles.is_empty() {
+                let roles: Vec<String> = form.roles.iter().map(|stats| format!("{} ({})", stats.role, stats.games)).collect();
+                lines.push(format!("**Roles** {}\n{}", roles.join(", "), player::main_role(&form.roles)));
+            }
+        }
+        Ok(None) => lines.push("**Recent games** None".to_string()),
+        Err(_) => lines.push("_Recent matches unavailable_".to_string()),
+    }
+
+    lines.join("\n")
+}
+
+/// Champions both players have mastery on, by combined points.
+fn shared_champions(first: &Player, second: &Player) -> String {
+    let (Ok(first), Ok(second)) = (&first.masteries, &second.masteries) else {
+        return "_Unavailable_".to_string();
+    };
+
+    let mut shared: Vec<(&ChampionMastery, &ChampionMastery)> = first
+        .iter()
+        .filter_map(|mine| Some((mine, second.iter().find(|theirs| theirs.champion_id == mine.champion_id)?)))
+        .collect();
+    shared.sort_by_key(|(mine, theirs)| std::cmp::Reverse(mine.champion_points + theirs.champion_points));
+
+    if shared.is_empty() {
+        return "None".to_string();
+    }
+    shared
+        .iter()
+        .take(SHARED_CHAMPIONS)
+        .map(|(mine, theirs)| {
+            format!(
+                "{}: {} / {}",
+                ddragon::champion_name(mine.champion_id),
+                mine.champion_points,
+                theirs.champion_points
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,177 @@
+use lazy_static::lazy_static;
+use serenity::all::{CreateActionRow, CreateButton, MessageId};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::AbortHandle;
+
+/// How long buttons and menus stay usable. Interaction tokens last 15 minutes, so components sent
+/// as interaction responses can only be disabled within that window.
+pub const COMPONENT_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Discord's limit on the length of a `custom_id`.
+pub const MAX_CUSTOM_ID: usize = 100;
+
+/// The `custom_id` of a button or menu: `<command>:<action>:<expires>:<state>`. The command name
+/// routes presses to the owning command, `expires` is a Unix timestamp and `state` is whatever the
+/// command needs to handle the press, since nothing else survives between presses or restarts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentId {
+    pub command: String,
+    pub action: String,
+    pub expires: u64,
+    pub state: String,
+}
+
+impl ComponentId {
+    /// An id that expires after [`COMPONENT_TTL`].
+    pub fn new(command: &str, action: &str, state: impl Into<String>) -> Self {
+        ComponentId {
+            command: command.to_string(),
+            action: action.to_string(),
+            expires: unix_now() + COMPONENT_TTL.as_secs(),
+            state: state.into(),
+        }
+    }
+
+    /// The same component with different state, keeping the expiry so a message's components all
+    /// expire together.
+    pub fn with_state(&self, state: impl Into<String>) -> Self {
+        ComponentId { state: state.into(), ..self.clone() }
+    }
+
+    pub fn parse(custom_id: &str) -> Option<Self> {
+        let mut parts = custom_id.splitn(4, ':');
+        Some(ComponentId {
+            command: parts.next()?.to_string(),
+            action: parts.next()?.to_string(),
+            expires: parts.next()?.parse().ok()?,
+            state: parts.next()?.to_string(),
+        })
+    }
+
+    pub fn is_expired(&self) -> bool {
+        unix_now() >= self.expires
+    }
+
+    /// Encodes the id. Discord limits `custom_id` to [`MAX_CUSTOM_ID`] characters and rejects the
+    /// whole message over it, so state that does not fit is cut short; the command then sees the
+    /// shortened state when the component is pressed.
+    pub fn encode(&self) -> String {
+        let prefix = format!("{}:{}:{}:", self.command, self.action, self.expires);
+        let room = MAX_CUSTOM_ID.saturating_sub(prefix.chars().count());
+        if self.state.chars().count() > room {
+            tracing::warn!("Component state for {} is too long, cutting it short: {}", self.command, self.state);
+        }
+        prefix + &self.state.chars().take(room).collect::<String>()
+    }
+}
+
+/// A press on one of a command's components.
+pub struct ComponentPress {
+    pub id: ComponentId,
+    /// Selected options for select menus, empty for buttons.
+    pub values: Vec<String>,
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or_default()
+}
+
+/// The earliest expiry among the components in `rows`, if any of them carry a [`ComponentId`].
+pub fn earliest_expiry(rows: &[CreateActionRow]) -> Option<u64> {
+    let rows = serde_json::to_value(rows).ok()?;
+    rows.as_array()?
+        .iter()
+        .filter_map(|row| row.get("components")?.as_array())
+        .flatten()
+        .filter_map(|component| ComponentId::parse(component.get("custom_id")?.as_str()?))
+        .map(|id| id.expires)
+        .min()
+}
+
+/// The same rows with every button and menu disabled.
+pub fn disabled(rows: &[CreateActionRow]) -> Vec<CreateActionRow> {
+    rows.iter()
+        .map(|row| match row {
+            CreateActionRow::Buttons(buttons) => {
+                CreateActionRow::Buttons(buttons.iter().cloned().map(|button: CreateButton| button.disabled(true)).collect())
+            }
+            CreateActionRow::SelectMenu(menu) => {
+                CreateActionRow::SelectMenu(menu.clone().disabled(true))
+            }
+            other => other.clone(),
+        })
+        .collect()
+}
+
+lazy_static! {
+    /// The pending expiry of every message with components, so each edit replaces the previous one.
+    static ref EXPIRY_TASKS: Mutex<HashMap<MessageId, (u64, AbortHandle)>> = Mutex::new(HashMap::new());
+}
+
+static NEXT_EXPIRY_TASK: AtomicU64 = AtomicU64::new(0);
+
+/// Once the components in `rows` expire, calls `disable` with the disabled rows so message
+/// `message_id` stops offering them. Replaces any expiry already pending for the message, so
+/// editing a message whose new rows carry no expiry cancels it.
+pub fn disable_on_expiry<F, Fut>(message_id: MessageId, rows: &[CreateActionRow], disable: F)
+where
+    F: FnOnce(Vec<CreateActionRow>) -> Fut + Send + 'static,
+    Fut: Future<Output = serenity::Result<()>> + Send,
+{
+    let mut tasks = EXPIRY_TASKS.lock().unwrap();
+    if let Some((_, pending)) = tasks.remove(&message_id) {
+        pending.abort();
+    }
+
+    let Some(expires) = earliest_expiry(rows) else {
+        return;
+    };
+    let rows = disabled(rows);
+    let wait = Duration::from_secs(expires.saturating_sub(unix_now()));
+    let task_id = NEXT_EXPIRY_TASK.fetch_add(1, Ordering::Relaxed);
+
+    let task = tokio::spawn(async move {
+        tokio::time::sleep(wait).await;
+        if let Err(why) = disable(rows).await {
+            tracing::debug!("Could not disable expired components: {:?}", why);
+        }
+
+        let mut tasks = EXPIRY_TASKS.lock().unwrap();
+        if tasks.get(&message_id).is_some_and(|(id, _)| *id == task_id) {
+            tasks.remove(&message_id);
+        }
+    });
+    tasks.insert(message_id, (task_id, task.abort_handle()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_survive_encoding() {
+        let id = ComponentId::new("champion", "page", "103:2:a:b");
+        assert_eq!(ComponentId::parse(&id.encode()), Some(id.clone()));
+
+        let empty = id.with_state("");
+        assert_eq!(ComponentId::parse(&empty.encode()), Some(empty));
+        assert_eq!(ComponentId::parse("champion:page"), None);
+        assert_eq!(ComponentId::parse("champion:page:soon:state"), None);
+    }
+
+    #[test]
+    fn long_state_is_cut_to_fit() {
+        let id = ComponentId::new("inhouse", "reshuffle", "é".repeat(200));
+        let custom_id = id.encode();
+        assert_eq!(custom_id.chars().count(), MAX_CUSTOM_ID);
+
+        let parsed = ComponentId::parse(&custom_id).unwrap();
+        assert_eq!((parsed.command.as_str(), parsed.action.as_str()), ("inhouse", "reshuffle"));
+        assert_eq!(parsed.expires, id.expires);
+        assert!(id.state.starts_with(&parsed.state));
+    }
+}
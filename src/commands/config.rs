@@ -0,0 +1,300 @@
+use serenity::all::GuildId;
+use serenity::async_trait;
+use riven::consts::PlatformRoute;
+
+use crate::commands::{self, Command, Invocation, Reply};
+use crate::permissions::PermissionLevel;
+use crate::riot::region;
+use crate::settings::{GuildSettings, SETTINGS};
+
+const USAGE: &str = "Usage:\n\
+    `config` - show the current settings\n\
+    `config region <code>` - set the default region (e.g. `EUW1` or `euw`)\n\
+    `config prefix <add|remove> <prefix>` / `config prefix reset` - manage the command prefixes\n\
+    `config channels <add|remove> <#channel>` / `config channels clear` - restrict the bot to channels\n\
+    `config feed <#channel|off>` - set the channel for announcements\n\
+    `config rotation <on|off>` - announce the free champion rotation in the feed channel\n\
+    `config status <on|off>` - post server incidents and maintenances in the feed channel\n\
+    `config language <code>` - set the language (e.g. `en`)\n\
+    `config <enable|disable> <command>` - toggle a command\n\
+    `config adminrole <@role|off>` - allow a role to manage the bot";
+
+pub struct ConfigCommand;
+
+impl ConfigCommand {
+    pub fn new() -> Self {
+        ConfigCommand
+    }
+}
+
+/// Extracts the id from a raw id or a channel/role mention such as `<#123>` or `<@&123>`.
+fn parse_id(value: &str) -> Option<u64> {
+    value
+        .trim_start_matches("<#")
+        .trim_start_matches("<@&")
+        .trim_end_matches('>')
+        .parse()
+        .ok()
+}
+
+fn describe(settings: &GuildSettings, region: PlatformRoute) -> String {
+    let channels = if settings.allowed_channels.is_empty() {
+        "all".to_string()
+    } else {
+        settings.allowed_channels.iter().map(|id| format!("<#{}>", id)).collect::<Vec<_>>().join(", ")
+    };
+    let disabled = if settings.disabled_commands.is_empty() {
+        "none".to_string()
+    } else {
+        settings.disabled_commands.iter().map(|name| format!("`{}`", name)).collect::<Vec<_>>().join(", ")
+    };
+
+    format!(
+        ":gear:**Server settings**\n\
+         Region: `{}`\n\
+         Prefixes: {}\n\
+         Allowed channels: {}\n\
+         Feed channel: {}\n\
+         Rotation announcements: {}\n\
+         Status alerts: {}\n\
+         Language: `{}`\n\
+         Disabled commands: {}\n\
+         Admin role: {}",
+        region,
+        settings.prefixes().iter().map(|prefix| format!("`{}`", prefix)).collect::<Vec<_>>().join(", "),
+        channels,
+        settings.feed_channel.map(|id| format!("<#{}>", id)).unwrap_or_else(|| "none".to_string()),
+        if settings.rotation_announcements { "on" } else { "off" },
+        if settings.status_alerts { "on" } else { "off" },
+        settings.language,
+        disabled,
+        settings.admin_role.map(|id| format!("<@&{}>", id)).unwrap_or_else(|| "none".to_string()),
+    )
+}
+
+/// Applies a `config` subcommand to the guild and returns the reply text.
+fn run_config(guild_id: GuildId, input: &str) -> String {
+    let parts: Vec<&str> = input.split_whitespace().collect();
+    let (setting, args) = match parts.split_first() {
+        Some((setting, args)) => (setting.to_lowercase(), args),
+        None => {
+            let region = crate::commands::region::RegionCommand::get_region(Some(guild_id));
+            return describe(&SETTINGS.get(Some(guild_id)), region);
+        }
+    };
+
+    match (setting.as_str(), args) {
+        ("region", [_, ..]) => match region::parse(&args.join(" ")) {
+            Ok(platform) => {
+                SETTINGS.update(guild_id, |settings| settings.region = Some(platform.to_string()));
+                format!("Default region set to `{}`.", region::label(platform))
+            }
+            Err(why) => why.to_string(),
+        },
+        ("prefix", ["reset"]) => {
+            SETTINGS.update(guild_id, |settings| settings.prefixes.clear());
+            format!("Prefixes reset to `{}`.", crate::config::get().discord.prefixes.join("`, `"))
+        }
+        ("prefix", ["add", prefix]) => {
+            let prefix = prefix.to_lowercase();
+            let previous = SETTINGS.get(Some(guild_id)).prefixes;
+            let reply = if previous.is_empty() {
+                format!(
+                    "Prefix `{}` added. It replaces the default `{}` on this server.",
+                    prefix,
+                    crate::config::get().discord.prefixes.join("`, `")
+                )
+            } else {
+                format!("Prefix `{}` added.", prefix)
+            };
+            SETTINGS.update(guild_id, |settings| {
+                settings.prefixes.retain(|existing| !existing.eq_ignore_ascii_case(&prefix));
+                settings.prefixes.push(prefix);
+            });
+            reply
+        }
+        ("prefix", ["remove", prefix]) => {
+            let prefix = prefix.to_lowercase();
+            let mut removed = false;
+            let settings = SETTINGS.update(guild_id, |settings| {
+                let before = settings.prefixes.len();
+                settings.prefixes.retain(|existing| !existing.eq_ignore_ascii_case(&prefix));
+                removed = settings.prefixes.len() < before;
+            });
+            match (removed, settings.prefixes.is_empty()) {
+                (false, _) => format!("`{}` is not one of this server's custom prefixes.", prefix),
+                (true, false) => format!("Prefix `{}` removed.", prefix),
+                (true, true) => format!(
+                    "Prefix `{}` removed. Without custom prefixes the default `{}` applies again.",
+                    prefix,
+                    crate::config::get().discord.prefixes.join("`, `")
+                ),
+            }
+        }
+        ("channels", ["clear"]) => {
+            SETTINGS.update(guild_id, |settings| settings.allowed_channels.clear());
+            "The bot can now be used in all channels.".to_string()
+        }
+        ("channels", [action @ ("add" | "remove"), channel]) => match parse_id(channel) {
+            Some(channel_id) => {
+                SETTINGS.update(guild_id, |settings| {
+                    settings.allowed_channels.retain(|id| *id != channel_id);
+                    if *action == "add" {
+                        settings.allowed_channels.push(channel_id);
+                    }
+                });
+                if *action == "add" {
+                    format!("The bot is now allowed in <#{}>.", channel_id)
+                } else {
+                    format!("The bot is no longer allowed in <#{}>.", channel_id)
+                }
+            }
+            None => format!("`{}` is not a channel.", channel),
+        },
+        ("feed", ["off"]) => {
+            SETTINGS.update(guild_id, |settings| settings.feed_channel = None);
+            "Feed channel disabled.".to_string()
+        }
+        ("feed", [channel]) => match parse_id(channel) {
+            Some(channel_id) => {
+                SETTINGS.update(guild_id, |settings| settings.feed_channel = Some(channel_id));
+                format!("Announcements will be posted in <#{}>.", channel_id)
+            }
+            None => format!("`{}` is not a channel.", channel),
+        },
+        ("rotation", [toggle @ ("on" | "off")]) => {
+            let enabled = *toggle == "on";
+            let settings = SETTINGS.update(guild_id, |settings| settings.rotation_announcements = enabled);
+            match (enabled, settings.feed_channel) {
+                (false, _) => "Rotation announcements disabled.".to_string(),
+                (true, Some(channel_id)) => format!("New free champion rotations will be posted in <#{}>.", channel_id),
+                (true, None) => "Rotation announcements enabled. Set a feed channel with `config feed <#channel>` to receive them.".to_string(),
+            }
+        }
+        ("status", [toggle @ ("on" | "off")]) => {
+            let enabled = *toggle == "on";
+            let settings = SETTINGS.update(guild_id, |settings| settings.status_alerts = enabled);
+            match (enabled, settings.feed_channel) {
+                (false, _) => "Status alerts disabled.".to_string(),
+                (true, Some(channel_id)) => format!("Server incidents will be posted in <#{}>.", channel_id),
+                (true, None) => "Status alerts enabled. Set a feed channel with `config feed <#channel>` to receive them.".to_string(),
+            }
+        }
+        ("language", [language]) => {
+            let language = language.to_lowercase();
+            let reply = format!("Language set to `{}`.", language);
+            SETTINGS.update(guild_id, |settings| settings.language = language);
+            reply
+        }
+        (action @ ("enable" | "disable"), [name]) => {
+            let name = name.to_lowercase();
+            if !commands::all().contains_key(&name) {
+                return format!("Unknown command `{}`.", name);
+            }
+            if name == "config" {
+                return "The `config` command cannot be disabled.".to_string();
+            }
+
+            let enable = action == "enable";
+            SETTINGS.update(guild_id, |settings| {
+                settings.disabled_commands.retain(|disabled| *disabled != name);
+                if !enable {
+                    settings.disabled_commands.push(name.clone());
+                }
+            });
+            format!("Command `{}` {}.", name, if enable { "enabled" } else { "disabled" })
+        }
+        ("adminrole", ["off"]) => {
+            SETTINGS.update(guild_id, |settings| settings.admin_role = None);
+            "Admin role removed.".to_string()
+        }
+        ("adminrole", [role]) => match parse_id(role) {
+            Some(role_id) => {
+                SETTINGS.update(guild_id, |settings| settings.admin_role = Some(role_id));
+                format!("Members with <@&{}> can now manage the bot.", role_id)
+            }
+            None => format!("`{}` is not a role.", role),
+        },
+        _ => USAGE.to_string(),
+    }
+}
+
+#[async_trait]
+impl Command for ConfigCommand {
+    async fn run(&self, invocation: &Invocation<'_>) {
+        let response = match invocation.guild_id {
+            Some(guild_id) => run_config(guild_id, invocation.input),
+            None => "The `config` command can only be used in a server.".to_string(),
+        };
+
+        invocation.send(Reply::text(response).ephemeral(true)).await;
+    }
+
+    fn name(&self) -> &str {
+        "config"
+    }
+
+    fn description(&self) -> &str {
+        "Manage the bot settings for this server."
+    }
+
+    fn permission(&self) -> PermissionLevel {
+        PermissionLevel::Admin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_the_region_and_rejects_unknown_ones() {
+        let guild_id = GuildId::new(2611);
+
+        assert_eq!(run_config(guild_id, "region euw"), "Default region set to `EUW (EUW1)`.");
+        assert_eq!(SETTINGS.get(Some(guild_id)).region(), Some(PlatformRoute::EUW1));
+        assert!(!run_config(guild_id, "region atlantis").starts_with("Default region set"));
+        assert_eq!(SETTINGS.get(Some(guild_id)).region(), Some(PlatformRoute::EUW1));
+    }
+
+    #[test]
+    fn custom_prefixes_replace_the_defaults() {
+        let guild_id = GuildId::new(2612);
+
+        assert!(run_config(guild_id, "prefix add !").contains("replaces the default"));
+        assert_eq!(run_config(guild_id, "prefix add ?"), "Prefix `?` added.");
+        assert_eq!(SETTINGS.get(Some(guild_id)).prefixes(), ["!", "?"]);
+
+        assert_eq!(run_config(guild_id, "prefix remove ."), "`.` is not one of this server's custom prefixes.");
+        assert_eq!(run_config(guild_id, "prefix remove !"), "Prefix `!` removed.");
+        assert!(run_config(guild_id, "prefix remove ?").contains("applies again"));
+        assert_eq!(SETTINGS.get(Some(guild_id)).prefixes(), crate::config::get().discord.prefixes);
+    }
+
+    #[test]
+    fn toggles_commands_except_config() {
+        let guild_id = GuildId::new(2613);
+
+        assert_eq!(run_config(guild_id, "disable Profile"), "Command `profile` disabled.");
+        assert!(!SETTINGS.get(Some(guild_id)).is_command_enabled("profile"));
+        assert_eq!(run_config(guild_id, "enable profile"), "Command `profile` enabled.");
+        assert!(SETTINGS.get(Some(guild_id)).is_command_enabled("profile"));
+
+        assert_eq!(run_config(guild_id, "disable config"), "The `config` command cannot be disabled.");
+        assert_eq!(run_config(guild_id, "disable nothing"), "Unknown command `nothing`.");
+    }
+
+    #[test]
+    fn parses_channels_and_roles() {
+        let guild_id = GuildId::new(2614);
+
+        assert_eq!(run_config(guild_id, "channels add <#42>"), "The bot is now allowed in <#42>.");
+        assert!(SETTINGS.get(Some(guild_id)).is_channel_allowed(42));
+        assert!(!SETTINGS.get(Some(guild_id)).is_channel_allowed(43));
+        assert_eq!(run_config(guild_id, "channels add general"), "`general` is not a channel.");
+
+        assert_eq!(run_config(guild_id, "adminrole <@&77>"), "Members with <@&77> can now manage the bot.");
+        assert_eq!(SETTINGS.get(Some(guild_id)).admin_role, Some(77));
+        assert_eq!(run_config(guild_id, "bogus"), USAGE);
+    }
+}
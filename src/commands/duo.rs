@@ -0,0 +1,227 @@
+use anyhow::Result;
+use riven::consts::PlatformRoute;
+use riven::models::account_v1::Account;
+use riven::models::match_v5::{Match, Participant};
+use serenity::all::CreateEmbed;
+use serenity::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::commands::region::RegionCommand;
+use crate::commands::{Command, Invocation, Reply};
+use crate::riot::matches::{self, Kind};
+use crate::riot::player;
+use crate::riot::{self, region};
+
+const USAGE: &str = "Usage: `duo <name#tag> [name#tag] [games]`, e.g. `duo Faker#KR1` or `duo Faker#KR1 Keria#KR1 50`";
+const DEFAULT_GAMES: i32 = 20;
+/// Teammates from a single game are usually random, so the list only shows repeated partners.
+const MIN_GAMES: usize = 2;
+const MAX_PARTNERS: usize = 10;
+
+pub struct DuoCommand;
+
+impl DuoCommand {
+    pub fn new() -> Self {
+        DuoCommand
+    }
+}
+
+#[async_trait]
+impl Command for DuoCommand {
+    async fn run(&self, invocation: &Invocation<'_>) {
+        let (input, platform) = match RegionCommand::resolve(invocation.guild_id, invocation.input) {
+            Ok(resolved) => resolved,
+            Err(reply) => {
+                invocation.send(reply).await;
+                return;
+            }
+        };
+
+        let mut ids = player::split_riot_ids(&input);
+        let count = match ids.last().and_then(|last| last.parse::<i32>().ok()) {
+            Some(count) => {
+                ids.pop();
+                count.clamp(1, matches::MAX_COUNT)
+            }
+            None => DEFAULT_GAMES,
+        };
+        let (riot_id, partner) = match ids[..] {
+            [riot_id] => (riot_id, None),
+            [riot_id, partner] => (riot_id, Some(partner)),
+            _ => {
+                invocation.send(USAGE).await;
+                return;
+            }
+        };
+
+        invocation.defer(false).await;
+
+        let response = match run_duo(platform, riot_id, partner, count).await {
+            Ok(response) => response,
+            Err(why) => {
+                tracing::warn!("Error fetching duo statistics: {:#}", why);
+                riot::error_reply(&why, "Failed to fetch duo statistics.").into()
+            }
+        };
+        invocation.send(response).await;
+    }
+
+    fn name(&self) -> &str {
+        "duo"
+    }
+
+    fn description(&self) -> &str {
+        "Show who a player queues with most in ranked, or how a pair does together."
+    }
+
+    fn takes_region(&self) -> bool {
+        true
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(15)
+    }
+}
+
+/// Games played on the same team, with both players' summed stats.
+struct Together {
+    riot_id: String,
+    games: usize,
+    wins: usize,
+    /// Kills, deaths and assists of the player the command is about.
+    own: [i32; 3],
+    partner: [i32; 3],
+}
+
+impl Together {
+    fn new(riot_id: String) -> Self {
+        Together { riot_id, games: 0, wins: 0, own: [0; 3], partner: [0; 3] }
+    }
+
+    fn add(&mut self, own: &Participant, partner: &Participant) {
+        self.games += 1;
+        self.wins += own.win as usize;
+        for (sum, stat) in self.own.iter_mut().zip([own.kills, own.deaths, own.assists]) {
+            *sum += stat;
+        }
+        for (sum, stat) in self.partner.iter_mut().zip([partner.kills, partner.deaths, partner.assists]) {
+            *sum += stat;
+        }
+    }
+
+    fn describe(&self, name: &str) -> String {
+        let average = |sums: &[i32; 3]| {
+            let games = self.games.max(1) as f64;
+            format!("{:.1}/{:.1}/{:.1}", sums[0] as f64 / games, sums[1] as f64 / games, sums[2] as f64 / games)
+        };
+        format!(
+            "{} {}, {}W {}L ({}%)\nKDA: {} {}, {} {}",
+            self.games,
+            if self.games == 1 { "game" } else { "games" },
+            self.wins,
+            self.games - self.wins,
+            player::win_rate(self.wins as u32, (self.games - self.wins) as u32),
+            name,
+            average(&self.own),
+            self.riot_id,
+            average(&self.partner),
+        )
+    }
+}
+
+async fn run_duo(platform: PlatformRoute, riot_id: &str, partner: Option<&str>, count: i32) -> Result<Reply> {
+    let riot_api = riot::api()?;
+
+    let (account, partner_account) = futures::join!(find(platform, riot_id), async {
+        match partner {
+            Some(partner) => find(platform, partner).await.map(Some),
+            None => Ok(None),
+        }
+    });
+    let account = match account? {
+        Ok(account) => account,
+        Err(reply) => return Ok(reply),
+    };
+    let partner_account = match partner_account? {
+        Some(Err(reply)) => return Ok(reply),
+        Some(Ok(partner_account)) => Some(partner_account),
+        None => None,
+    };
+
+    let history = matches::recent(riot_api, platform, &account.puuid, Kind::Ranked, count).await?;
+    let games = &history.games;
+    let name = player::riot_id(&account);
+    let mut partners = partners(games, &account.puuid);
+    let note = history.skipped_note().map(|note| format!("\n_{}_", note)).unwrap_or_default();
+
+    let title = format!("{} in the last {} ranked games{}", name, games.len(), note);
+    let embed = match partner_account {
+        Some(partner_account) => {
+            let partner_name = player::riot_id(&partner_account);
+            let Some(together) = partners.remove(&partner_account.puuid) else {
+                return Ok(format!(
+                    "{} and {} did not play together in {}'s last {} ranked games.{}",
+                    name,
+                    partner_name,
+                    name,
+                    games.len(),
+                    note
+                )
+                .into());
+            };
+            CreateEmbed::new()
+                .title(format!("{} with {}", name, partner_name))
+                .description(format!("{}\n{}", region::label(platform), title))
+                .field("Together", together.describe(&name), false)
+        }
+        None => {
+            let mut partners: Vec<Together> = partners.into_values().filter(|together| together.games >= MIN_GAMES).collect();
+            if partners.is_empty() {
+                return Ok(format!("{} did not play with anyone more than once in their last {} ranked games.{}", name, games.len(), note).into());
+            }
+            partners.sort_by(|a, b| b.games.cmp(&a.games).then(b.wins.cmp(&a.wins)));
+
+            let mut embed = CreateEmbed::new()
+                .title(format!("Duo partners of {}", name))
+                .description(format!("{}\n{}", region::label(platform), title));
+            for together in partners.iter().take(MAX_PARTNERS) {
+                embed = embed.field(&together.riot_id, together.describe(&name), false);
+            }
+            embed
+        }
+    };
+
+    Ok(Reply::default().embed(embed))
+}
+
+/// The account for `riot_id`, or a reply saying there is none.
+async fn find(platform: PlatformRoute, riot_id: &str) -> Result<Result<Account, Reply>> {
+    let (game_name, tag_line) = player::parse_riot_id(riot_id);
+    Ok(match player::account(riot::api()?, platform, game_name, tag_line).await? {
+        Some(account) => Ok(account),
+        None => Err(format!("There is no summoner named `{}`.", riot_id).into()),
+    })
+}
+
+/// Everyone who was on the player's team, by PUUID.
+fn partners(games: &[Arc<Match>], puuid: &str) -> HashMap<String, Together> {
+    let mut partners: HashMap<String, Together> = HashMap::new();
+    for game in games {
+        let Some(own) = matches::participant(game, puuid) else { continue };
+        for partner in game.info.participants.iter().filter(|other| other.team_id == own.team_id && other.puuid != puuid) {
+            partners
+                .entry(partner.puuid.clone())
+                .or_insert_with(|| {
+                    Together::new(format!(
+                        "{}#{}",
+                        partner.riot_id_game_name.as_deref().unwrap_or(&partner.summoner_name),
+                        partner.riot_id_tagline
+                    ))
+                })
+                .add(own, partner);
+        }
+    }
+    partners
+}
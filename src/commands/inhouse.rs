@@ -0,0 +1,467 @@
+use anyhow::Result;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use lazy_static::lazy_static;
+use riven::consts::PlatformRoute;
+use serenity::all::{ButtonStyle, CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter};
+use serenity::async_trait;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::commands::components::unix_now;
+use crate::commands::region::RegionCommand;
+use crate::commands::{Command, ComponentId, ComponentPress, Invocation, Reply};
+use crate::riot::matches::{self, Kind};
+use crate::riot::player;
+use crate::riot::{self, region};
+use crate::settings::{GuildSettings, SETTINGS};
+
+const USAGE: &str = "Usage: `inhouse <name#tag> ...` with the Riot IDs of all ten players, or mentions of members \
+    who linked theirs with `link`, e.g. `inhouse Faker#KR1 Chovy#KR1 Keria#KR1 Zeus#KR1 Oner#KR1 Gumayusi#KR1 Canyon#KR1 Peyz#KR1 Kiin#KR1 Doran#KR1`";
+const TEAM_SIZE: usize = 5;
+const PLAYERS: usize = 2 * TEAM_SIZE;
+const ROLES: [&str; TEAM_SIZE] = ["Top", "Jungle", "Mid", "Bot", "Support"];
+/// Ranked games to read preferred roles from.
+const ROLE_GAMES: i32 = 10;
+/// Players looked up at a time. Each one loads [`ROLE_GAMES`] matches, so more would mostly wait on
+/// the rate limits and give up.
+const CONCURRENT_PLAYERS: usize = 2;
+/// Unranked players count as Gold IV.
+const UNRANKED_RATING: i32 = 3 * 400;
+/// How much average rating difference one step down a player's role list is worth.
+const ROLE_WEIGHT: i32 = 60;
+/// Cost of a role the player did not play at all in their recent games.
+const UNPLAYED_ROLE: i32 = 3;
+/// Rating difference that makes one team a 10:1 favourite, for the expected win chance.
+const ODDS_SCALE: f64 = 2000.0;
+
+lazy_static! {
+    /// Lobbies by id. Ten players do not fit into a 100-character `custom_id`, so the reshuffle
+    /// button only carries the lobby id and the split shown, and lobbies are kept until their
+    /// buttons expire.
+    static ref LOBBIES: Mutex<HashMap<u64, Lobby>> = Mutex::new(HashMap::new());
+}
+
+static NEXT_LOBBY: AtomicU64 = AtomicU64::new(1);
+
+pub struct InhouseCommand;
+
+impl InhouseCommand {
+    pub fn new() -> Self {
+        InhouseCommand
+    }
+}
+
+#[async_trait]
+impl Command for InhouseCommand {
+    async fn run(&self, invocation: &Invocation<'_>) {
+        let (input, platform) = match RegionCommand::resolve(invocation.guild_id, invocation.input) {
+            Ok(resolved) => resolved,
+            Err(reply) => {
+                invocation.send(reply).await;
+                return;
+            }
+        };
+        let input = match resolve_mentions(&input.replace(',', " "), &SETTINGS.get(invocation.guild_id)) {
+            Ok(input) => input,
+            Err(unlinked) => {
+                invocation.send(format!(
+                    "{} {} not linked a Riot ID on this server. Members can link theirs with `link <name#tag>`.",
+                    unlinked.join(", "),
+                    if unlinked.len() == 1 { "has" } else { "have" }
+                ))
+                .await;
+                return;
+            }
+        };
+        let ids = player::split_riot_ids(&input);
+        if ids.len() != PLAYERS {
+            invocation.send(format!("Found {} players, but an in-house needs {}.\n{}", ids.len(), PLAYERS, USAGE)).await;
+            return;
+        }
+        for (index, id) in ids.iter().enumerate() {
+            if ids[..index].iter().any(|other| other.eq_ignore_ascii_case(id)) {
+                invocation.send(format!("`{}` is listed more than once.", id)).await;
+                return;
+            }
+        }
+
+        invocation.defer(false).await;
+
+        let members = match fetch_members(platform, &ids).await {
+            Ok(Ok(members)) => members,
+            Ok(Err(unknown)) => {
+                invocation.send(format!("There are no summoners named {}.", unknown.join(", "))).await;
+                return;
+            }
+            Err(why) => {
+                tracing::warn!("Error fetching in-house players: {:#}", why);
+                invocation.send(riot::error_reply(&why, "Failed to fetch the players.")).await;
+                return;
+            }
+        };
+
+        let id = ComponentId::new(self.name(), "reshuffle", "");
+        let lobby = Lobby { platform, expires: id.expires, splits: balance(&members), members };
+        let lobby_id = NEXT_LOBBY.fetch_add(1, Ordering::Relaxed);
+        let reply = render(&lobby, 0, &id.with_state(encode_state(lobby_id, 0)));
+
+        {
+            let mut lobbies = LOBBIES.lock().unwrap();
+            let now = unix_now();
+            lobbies.retain(|_, lobby| lobby.expires > now);
+            lobbies.insert(lobby_id, lobby);
+        }
+
+        invocation.send(reply).await;
+    }
+
+    async fn handle_component(&self, invocation: &Invocation<'_>, press: &ComponentPress) {
+        let reply = parse_state(&press.id.state).and_then(|(lobby_id, shown)| {
+            let lobbies = LOBBIES.lock().unwrap();
+            let lobby = lobbies.get(&lobby_id).filter(|lobby| lobby.expires == press.id.expires)?;
+            let next = (shown + 1) % lobby.splits.len();
+            Some(render(lobby, next, &press.id.with_state(encode_state(lobby_id, next))))
+        });
+
+        match reply {
+            Some(reply) => invocation.edit(reply).await,
+            None => {
+                tracing::debug!(state = %press.id.state, "In-house lobby is no longer available");
+                invocation.send(Reply::text("This menu is no longer active.").ephemeral(true)).await;
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "inhouse"
+    }
+
+    fn description(&self) -> &str {
+        "Split ten players into two balanced teams by rank and preferred roles."
+    }
+
+    fn takes_region(&self) -> bool {
+        true
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+}
+
+struct Member {
+    riot_id: String,
+    /// e.g. `DIAMOND I: 88 LP (Solo/Duo)`, or `Unranked`.
+    rank: String,
+    rating: i32,
+    /// Positions from recent ranked games, most played first. Empty players fill any role.
+    roles: Vec<&'static str>,
+    /// Whether some of their recent games could not be loaded, so `roles` may be off.
+    roles_incomplete: bool,
+}
+
+impl Member {
+    /// 0 for the most played role, more for each step down the list.
+    fn role_cost(&self, role: &str) -> i32 {
+        if self.roles.is_empty() {
+            return 0;
+        }
+        match self.roles.iter().position(|played| *played == role) {
+            Some(index) => (index as i32).min(UNPLAYED_ROLE),
+            None => UNPLAYED_ROLE,
+        }
+    }
+}
+
+/// One way to split the lobby, with each team's players in [`ROLES`] order.
+struct Split {
+    blue: [usize; TEAM_SIZE],
+    red: [usize; TEAM_SIZE],
+    /// Difference of the average ratings.
+    spread: i32,
+    /// Steps down their role lists, summed over all players.
+    off_role: i32,
+}
+
+struct Lobby {
+    platform: PlatformRoute,
+    expires: u64,
+    members: Vec<Member>,
+    /// Best first.
+    splits: Vec<Split>,
+}
+
+/// Replaces member mentions such as `<@123>` with the Riot IDs the members linked, or returns the
+/// mentions of members who have not linked one.
+fn resolve_mentions(input: &str, settings: &GuildSettings) -> Result<String, Vec<String>> {
+    let mut resolved = String::new();
+    let mut unlinked = Vec::new();
+    let mut rest = input;
+    while let Some(start) = rest.find("<@") {
+        resolved.push_str(&rest[..start]);
+        let mention = &rest[start..];
+        let end = mention.find('>').map_or(mention.len(), |end| end + 1);
+        let user_id = mention[2..end].trim_start_matches('!').trim_end_matches('>').parse::<u64>().ok();
+        match user_id.and_then(|user_id| settings.linked_accounts.get(&user_id)) {
+            // Spaces around the Riot ID keep it apart from the next one, whose name may have spaces
+            Some(riot_id) => resolved.push_str(&format!(" {} ", riot_id)),
+            None => unlinked.push(mention[..end].to_string()),
+        }
+        rest = &mention[end..];
+    }
+    resolved.push_str(rest);
+
+    if unlinked.is_empty() {
+        Ok(resolved)
+    } else {
+        Err(unlinked)
+    }
+}
+
+/// The players for `ids`, or the Riot IDs that do not exist.
+async fn fetch_members(platform: PlatformRoute, ids: &[&str]) -> Result<Result<Vec<Member>, Vec<String>>> {
+    let riot_api = riot::api()?;
+    // Collecting the futures first keeps the streams free of closures, which the command future
+    // needs to stay `Send`
+    let lookups: Vec<_> = ids
+        .iter()
+        .map(|id| {
+            let (game_name, tag_line) = player::parse_riot_id(id);
+            player::account(riot_api, platform, game_name, tag_line)
+        })
+        .collect();
+    let accounts: Vec<_> = stream::iter(lookups).buffered(CONCURRENT_PLAYERS).try_collect().await?;
+
+    let unknown: Vec<String> = ids
+        .iter()
+        .zip(&accounts)
+        .filter(|(_, account)| account.is_none())
+        .map(|(id, _)| format!("`{}`", id))
+        .collect();
+    if !unknown.is_empty() {
+        return Ok(Err(unknown));
+    }
+
+    let lookups: Vec<_> = accounts.into_iter().flatten().map(|account| async move {
+        let (summary, games) = futures::join!(
+            player::summary(riot_api, platform, &account.puuid),
+            matches::recent(riot_api, platform, &account.puuid, Kind::Ranked, ROLE_GAMES),
+        );
+        let summary = summary?;
+        // Without role data the player can fill any role, which is better than no teams at all
+        let (roles, roles_incomplete) = match games {
+            Ok(history) => (
+                player::roles(history.games.iter().filter_map(|game| matches::participant(game, &account.puuid))),
+                history.skipped > 0,
+            ),
+            Err(why) => {
+                tracing::warn!("Error fetching matches for {}: {:#}", player::riot_id(&account), why);
+                (Vec::new(), true)
+            }
+        };
+
+        // The rank shown is the one the rating comes from
+        let rated = summary.rated();
+        anyhow::Ok(Member {
+            riot_id: player::riot_id(&account),
+            rank: rated.map_or_else(|| "Unranked".to_string(), |rank| format!("{} ({})", rank.rank, rank.queue_name())),
+            rating: rated.and_then(|rank| rank.rating).unwrap_or(UNRANKED_RATING),
+            roles: roles.into_iter().map(|stats| stats.role).collect(),
+            roles_incomplete,
+        })
+    }).collect();
+    let members: Vec<Member> = stream::iter(lookups).buffered(CONCURRENT_PLAYERS).try_collect().await?;
+
+    Ok(Ok(members))
+}
+
+/// Every split of the lobby into two teams, best first: the lowest average rating difference, with
+/// each step off a player's preferred roles counting as [`ROLE_WEIGHT`].
+fn balance(members: &[Member]) -> Vec<Split> {
+    let mut splits = Vec::new();
+    // Keeping the first player on blue visits each split once instead of once per side
+    for mask in (0u32..1 << PLAYERS).filter(|mask| mask.count_ones() as usize == TEAM_SIZE && mask & 1 == 1) {
+        let team = |blue: bool| -> [usize; TEAM_SIZE] {
+            let players: Vec<usize> = (0..PLAYERS).filter(|index| (mask >> index & 1 == 1) == blue).collect();
+            players.try_into().unwrap_or_default()
+        };
+        let (blue, blue_cost) = assign_roles(members, team(true));
+        let (red, red_cost) = assign_roles(members, team(false));
+        let spread = (average(members, &blue) - average(members, &red)).abs();
+        splits.push(Split { blue, red, spread, off_role: blue_cost + red_cost });
+    }
+    splits.sort_by_key(|split| (split.spread + ROLE_WEIGHT * split.off_role, split.spread));
+    splits
+}
+
+fn average(members: &[Member], team: &[usize; TEAM_SIZE]) -> i32 {
+    team.iter().map(|&index| members[index].rating).sum::<i32>() / TEAM_SIZE as i32
+}
+
+/// The team's players in [`ROLES`] order that keeps them closest to their preferred roles, and how
+/// far off it is.
+fn assign_roles(members: &[Member], team: [usize; TEAM_SIZE]) -> ([usize; TEAM_SIZE], i32) {
+    fn visit(members: &[Member], order: &mut [usize; TEAM_SIZE], filled: usize, best: &mut ([usize; TEAM_SIZE], i32)) {
+        if filled == TEAM_SIZE {
+            let cost = order.iter().zip(ROLES).map(|(&index, role)| members[index].role_cost(role)).sum();
+            if cost < best.1 {
+                *best = (*order, cost);
+            }
+            return;
+        }
+        for next in filled..TEAM_SIZE {
+            order.swap(filled, next);
+            visit(members, order, filled + 1, best);
+            order.swap(filled, next);
+        }
+    }
+
+    let mut best = (team, i32::MAX);
+    visit(members, &mut team.clone(), 0, &mut best);
+    best
+}
+
+fn render(lobby: &Lobby, shown: usize, id: &ComponentId) -> Reply {
+    let split = &lobby.splits[shown];
+    let (blue, red) = (average(&lobby.members, &split.blue), average(&lobby.members, &split.red));
+    let blue_odds = 1.0 / (1.0 + 10f64.powf((red - blue) as f64 / ODDS_SCALE));
+
+    let team = |players: &[usize; TEAM_SIZE]| {
+        players
+            .iter()
+            .zip(ROLES)
+            .map(|(&index, role)| {
+                let member = &lobby.members[index];
+                let marker = if member.role_cost(role) > 0 { "*" } else { "" };
+                format!("**{}{}** {}\n{}", role, marker, member.riot_id, member.rank)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let mut description = format!(
+        "{}\nOption {} of {} · Rating spread {} · Expected win chance {:.0}% / {:.0}%",
+        region::label(lobby.platform),
+        shown + 1,
+        lobby.splits.len(),
+        split.spread,
+        blue_odds * 100.0,
+        (1.0 - blue_odds) * 100.0
+    );
+    let incomplete: Vec<&str> =
+        lobby.members.iter().filter(|member| member.roles_incomplete).map(|member| member.riot_id.as_str()).collect();
+    if !incomplete.is_empty() {
+        description.push_str(&format!(
+            "\n_Some recent matches of {} could not be loaded, so their roles may be off._",
+            incomplete.join(", ")
+        ));
+    }
+
+    let embed = CreateEmbed::new()
+        .title("In-house teams")
+        .description(description)
+        .field(format!("🔵 Blue side ({})", blue), team(&split.blue), true)
+        .field(format!("🔴 Red side ({})", red), team(&split.red), true)
+        .footer(CreateEmbedFooter::new(
+            "Ratings are LP above Iron IV, with unranked players as Gold IV. * marks a role the player rarely or never plays.",
+        ));
+
+    let reshuffle = CreateButton::new(id.encode()).label("🔀 Reshuffle").style(ButtonStyle::Primary);
+    Reply::default().embed(embed).components(vec![CreateActionRow::Buttons(vec![reshuffle])])
+}
+
+fn encode_state(lobby_id: u64, shown: usize) -> String {
+    format!("{}.{}", lobby_id, shown)
+}
+
+fn parse_state(state: &str) -> Option<(u64, usize)> {
+    let (lobby_id, shown) = state.split_once('.')?;
+    Some((lobby_id.parse().ok()?, shown.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(rating: i32, roles: &[&'static str]) -> Member {
+        Member {
+            riot_id: format!("Player {}#TEST", rating),
+            rank: "Unranked".to_string(),
+            rating,
+            roles: roles.to_vec(),
+            roles_incomplete: false,
+        }
+    }
+
+    fn has_both(team: &[usize; TEAM_SIZE], first: usize, second: usize) -> bool {
+        team.contains(&first) && team.contains(&second)
+    }
+
+    #[test]
+    fn picks_the_split_with_the_smallest_rating_spread() {
+        let ratings = [400, 400, 800, 800, 1200, 1200, 1600, 1600, 2000, 2000];
+        let members: Vec<Member> = ratings.iter().map(|&rating| member(rating, &[])).collect();
+
+        let splits = balance(&members);
+        // Each split once, with the first player always on blue
+        assert_eq!(splits.len(), 126);
+        assert!(splits.iter().all(|split| split.blue.contains(&0)));
+        assert_eq!(splits[0].spread, 0);
+        assert_eq!(average(&members, &splits[0].blue), average(&members, &splits[0].red));
+    }
+
+    #[test]
+    fn reshuffles_through_the_next_best_splits_in_order() {
+        let mut members: Vec<Member> = (0..PLAYERS as i32).map(|index| member(1000 + 100 * index, &[])).collect();
+        members[0].roles = vec!["Mid", "Top"];
+        members[1].roles = vec!["Mid", "Jungle"];
+
+        let splits = balance(&members);
+        let cost = |split: &Split| split.spread + ROLE_WEIGHT * split.off_role;
+        assert!(splits.windows(2).all(|pair| (cost(&pair[0]), pair[0].spread) <= (cost(&pair[1]), pair[1].spread)));
+        assert_eq!(splits.last().map(|split| split.spread), splits.iter().map(|split| split.spread).max());
+    }
+
+    #[test]
+    fn separates_players_who_want_the_same_role() {
+        let mut members: Vec<Member> = (0..PLAYERS).map(|_| member(1500, &[])).collect();
+        members[0].roles = vec!["Mid", "Top"];
+        members[1].roles = vec!["Mid", "Jungle"];
+
+        let splits = balance(&members);
+        assert!(!has_both(&splits[0].blue, 0, 1) && !has_both(&splits[0].red, 0, 1));
+        assert_eq!(splits[0].off_role, 0);
+        let together = splits.iter().find(|split| has_both(&split.blue, 0, 1)).unwrap();
+        assert_eq!(together.off_role, 1);
+    }
+
+    #[test]
+    fn assigns_roles_around_conflicts() {
+        let members = vec![
+            member(1000, &["Mid", "Top"]),
+            member(1000, &["Mid", "Jungle"]),
+            member(1000, &["Bot"]),
+            member(1000, &["Bot"]),
+            member(1000, &[]),
+        ];
+
+        let (order, cost) = assign_roles(&members, [0, 1, 2, 3, 4]);
+        // One mid moves to their second role, and one bot laner to a role they never play
+        assert_eq!(cost, 1 + UNPLAYED_ROLE);
+        let role_of = |player: usize| ROLES[order.iter().position(|&index| index == player).unwrap()];
+        assert!(matches!((role_of(0), role_of(1)), ("Mid", "Jungle") | ("Top", "Mid")));
+        assert!(role_of(2) == "Bot" || role_of(3) == "Bot");
+    }
+
+    #[test]
+    fn replaces_linked_mentions_with_riot_ids() {
+        let mut settings = GuildSettings::default();
+        settings.linked_accounts.insert(1, "Hide on bush#KR1".to_string());
+
+        let resolved = resolve_mentions("<@1> <@!1>Chovy#KR1", &settings).unwrap();
+        assert_eq!(player::split_riot_ids(&resolved), ["Hide on bush#KR1", "Hide on bush#KR1", "Chovy#KR1"]);
+        assert_eq!(resolve_mentions("<@1> <@2> <@!3>", &settings).unwrap_err(), ["<@2>", "<@!3>"]);
+    }
+}
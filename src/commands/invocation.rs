@@ -0,0 +1,493 @@
+use serenity::all::{
+    ChannelId, CommandInteraction, ComponentInteraction, CreateActionRow, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateMessage, EditInteractionResponse,
+    EditMessage, GuildId, Http, MessageId, UserId,
+};
+use serenity::async_trait;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+use crate::commands::components;
+use crate::metrics;
+use crate::permissions::PermissionLevel;
+
+/// Everything a command can send back: text, embeds and components. `ephemeral` only applies to
+/// interactions; in channels the reply is visible to everyone.
+#[derive(Debug, Clone, Default)]
+pub struct Reply {
+    pub content: Option<String>,
+    pub embeds: Vec<CreateEmbed>,
+    pub components: Vec<CreateActionRow>,
+    pub ephemeral: bool,
+}
+
+impl Reply {
+    pub fn text(content: impl Into<String>) -> Self {
+        Reply { content: Some(content.into()), ..Default::default() }
+    }
+
+    pub fn embed(mut self, embed: CreateEmbed) -> Self {
+        self.embeds.push(embed);
+        self
+    }
+
+    pub fn components(mut self, components: Vec<CreateActionRow>) -> Self {
+        self.components = components;
+        self
+    }
+
+    pub fn ephemeral(mut self, ephemeral: bool) -> Self {
+        self.ephemeral = ephemeral;
+        self
+    }
+
+    fn to_message(&self) -> CreateMessage {
+        CreateMessage::new()
+            .content(self.content.clone().unwrap_or_default())
+            .embeds(self.embeds.clone())
+            .components(self.components.clone())
+    }
+
+    fn to_edit_message(&self) -> EditMessage {
+        EditMessage::new()
+            .content(self.content.clone().unwrap_or_default())
+            .embeds(self.embeds.clone())
+            .components(self.components.clone())
+    }
+
+    fn to_interaction_message(&self) -> CreateInteractionResponseMessage {
+        CreateInteractionResponseMessage::new()
+            .content(self.content.clone().unwrap_or_default())
+            .embeds(self.embeds.clone())
+            .components(self.components.clone())
+            .ephemeral(self.ephemeral)
+    }
+
+    fn to_interaction_edit(&self) -> EditInteractionResponse {
+        EditInteractionResponse::new()
+            .content(self.content.clone().unwrap_or_default())
+            .embeds(self.embeds.clone())
+            .components(self.components.clone())
+    }
+
+    fn to_followup(&self) -> CreateInteractionResponseFollowup {
+        CreateInteractionResponseFollowup::new()
+            .content(self.content.clone().unwrap_or_default())
+            .embeds(self.embeds.clone())
+            .components(self.components.clone())
+            .ephemeral(self.ephemeral)
+    }
+
+    /// Renders the reply as plain text for the terminal: the content, then each embed and the
+    /// labels of its buttons and menus.
+    pub fn render_text(&self) -> String {
+        let mut text = self.content.clone().unwrap_or_default();
+
+        for embed in &self.embeds {
+            let Ok(embed) = serde_json::to_value(embed) else { continue };
+            let field = |value: &Value, key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+
+            text.push_str("\n┌─");
+            if let Some(author) = embed.get("author").and_then(|author| field(author, "name")) {
+                text.push_str(&format!("\n│ {}", author));
+            }
+            if let Some(title) = field(&embed, "title") {
+                text.push_str(&format!("\n│ **{}**", title));
+            }
+            if let Some(description) = field(&embed, "description") {
+                text.push_str(&format!("\n│ {}", description.replace('\n', "\n│ ")));
+            }
+            for item in embed.get("fields").and_then(Value::as_array).into_iter().flatten() {
+                text.push_str(&format!(
+                    "\n│ {}: {}",
+                    field(item, "name").unwrap_or_default(),
+                    field(item, "value").unwrap_or_default().replace('\n', "\n│   ")
+                ));
+            }
+            if let Some(footer) = embed.get("footer").and_then(|footer| field(footer, "text")) {
+                text.push_str(&format!("\n│ _{}_", footer));
+            }
+            text.push_str("\n└─");
+        }
+
+        for row in &self.components {
+            let Ok(row) = serde_json::to_value(row) else { continue };
+            let labels: Vec<String> = row
+                .get("components")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|component| {
+                    let label = component.get("label").or_else(|| component.get("placeholder"))?.as_str()?;
+                    let disabled = component.get("disabled").and_then(Value::as_bool).unwrap_or(false);
+                    Some(if disabled { format!("[{} (disabled)]", label) } else { format!("[{}]", label) })
+                })
+                .collect();
+            if !labels.is_empty() {
+                text.push_str(&format!("\n{}", labels.join(" ")));
+            }
+        }
+
+        text
+    }
+}
+
+impl From<String> for Reply {
+    fn from(content: String) -> Self {
+        Reply::text(content)
+    }
+}
+
+impl From<&str> for Reply {
+    fn from(content: &str) -> Self {
+        Reply::text(content)
+    }
+}
+
+/// Where a command's replies go: a channel, an interaction response or, for the CLI, a buffer.
+#[async_trait]
+pub trait ReplySink: Send + Sync {
+    /// Acknowledges the invocation before slow work. Interactions get a "thinking" response, channels
+    /// a typing indicator.
+    async fn defer(&self, ephemeral: bool) -> serenity::Result<()>;
+
+    /// Sends a reply. After [`ReplySink::defer`] the first reply fills in the deferred response.
+    async fn send(&self, reply: Reply) -> serenity::Result<()>;
+
+    /// Replaces the last reply sent, or sends one if there is none yet.
+    async fn edit(&self, reply: Reply) -> serenity::Result<()>;
+}
+
+/// Replies in the channel a prefix command was sent in.
+pub struct MessageSink {
+    http: Arc<Http>,
+    channel_id: ChannelId,
+    last: Mutex<Option<MessageId>>,
+}
+
+impl MessageSink {
+    pub fn new(http: Arc<Http>, channel_id: ChannelId) -> Self {
+        MessageSink { http, channel_id, last: Mutex::new(None) }
+    }
+}
+
+#[async_trait]
+impl ReplySink for MessageSink {
+    async fn defer(&self, _ephemeral: bool) -> serenity::Result<()> {
+        self.channel_id.broadcast_typing(&self.http).await
+    }
+
+    async fn send(&self, reply: Reply) -> serenity::Result<()> {
+        let message = self.channel_id.send_message(&self.http, reply.to_message()).await?;
+        *self.last.lock().unwrap() = Some(message.id);
+        self.disable_on_expiry(message.id, &reply);
+        Ok(())
+    }
+
+    async fn edit(&self, reply: Reply) -> serenity::Result<()> {
+        let last = *self.last.lock().unwrap();
+        match last {
+            Some(message_id) => {
+                self.channel_id.edit_message(&self.http, message_id, reply.to_edit_message()).await?;
+                self.disable_on_expiry(message_id, &reply);
+                Ok(())
+            }
+            None => self.send(reply).await,
+        }
+    }
+}
+
+impl MessageSink {
+    fn disable_on_expiry(&self, message_id: MessageId, reply: &Reply) {
+        let (http, channel_id) = (self.http.clone(), self.channel_id);
+        components::disable_on_expiry(message_id, &reply.components, move |rows| async move {
+            channel_id.edit_message(&http, message_id, EditMessage::new().components(rows)).await.map(|_| ())
+        });
+    }
+}
+
+/// Disables the components of an interaction's original response, message `message_id`, once they
+/// expire.
+fn disable_response_on_expiry(http: &Arc<Http>, token: &str, message_id: MessageId, reply: &Reply) {
+    let (http, token) = (http.clone(), token.to_string());
+    components::disable_on_expiry(message_id, &reply.components, move |rows| async move {
+        let edit = EditInteractionResponse::new().components(rows);
+        http.edit_original_interaction_response(&token, &edit, Vec::new()).await.map(|_| ())
+    });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResponseState {
+    Pending,
+    Deferred,
+    Responded,
+}
+
+/// Replies to a slash command. Discord allows 3 seconds for the first response but 15 minutes for
+/// edits and followups, so slow commands should defer first.
+pub struct InteractionSink {
+    http: Arc<Http>,
+    interaction: CommandInteraction,
+    state: Mutex<ResponseState>,
+    /// The latest followup, which [`ReplySink::edit`] replaces instead of the original response.
+    last_followup: Mutex<Option<MessageId>>,
+}
+
+impl InteractionSink {
+    pub fn new(http: Arc<Http>, interaction: CommandInteraction) -> Self {
+        InteractionSink {
+            http,
+            interaction,
+            state: Mutex::new(ResponseState::Pending),
+            last_followup: Mutex::new(None),
+        }
+    }
+
+    fn state(&self) -> ResponseState {
+        *self.state.lock().unwrap()
+    }
+
+    fn set_state(&self, state: ResponseState) {
+        *self.state.lock().unwrap() = state;
+    }
+}
+
+#[async_trait]
+impl ReplySink for InteractionSink {
+    async fn defer(&self, ephemeral: bool) -> serenity::Result<()> {
+        if self.state() != ResponseState::Pending {
+            return Ok(());
+        }
+
+        let response = CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new().ephemeral(ephemeral));
+        self.interaction.create_response(&self.http, response).await?;
+        self.set_state(ResponseState::Deferred);
+        Ok(())
+    }
+
+    async fn send(&self, reply: Reply) -> serenity::Result<()> {
+        match self.state() {
+            ResponseState::Pending => {
+                let response = CreateInteractionResponse::Message(reply.to_interaction_message());
+                self.interaction.create_response(&self.http, response).await?;
+                // Creating a response does not return the message, which is only needed for components
+                if !reply.components.is_empty() {
+                    let message = self.interaction.get_response(&self.http).await?;
+                    disable_response_on_expiry(&self.http, &self.interaction.token, message.id, &reply);
+                }
+            }
+            ResponseState::Deferred => {
+                let message = self.interaction.edit_response(&self.http, reply.to_interaction_edit()).await?;
+                disable_response_on_expiry(&self.http, &self.interaction.token, message.id, &reply);
+            }
+            ResponseState::Responded => {
+                let message = self.interaction.create_followup(&self.http, reply.to_followup()).await?;
+                *self.last_followup.lock().unwrap() = Some(message.id);
+            }
+        }
+        self.set_state(ResponseState::Responded);
+        Ok(())
+    }
+
+    async fn edit(&self, reply: Reply) -> serenity::Result<()> {
+        let last_followup = *self.last_followup.lock().unwrap();
+        match (self.state(), last_followup) {
+            (ResponseState::Pending, _) => self.send(reply).await,
+            (ResponseState::Responded, Some(message_id)) => {
+                self.interaction.edit_followup(&self.http, message_id, reply.to_followup()).await?;
+                Ok(())
+            }
+            (ResponseState::Deferred | ResponseState::Responded, _) => {
+                let message = self.interaction.edit_response(&self.http, reply.to_interaction_edit()).await?;
+                disable_response_on_expiry(&self.http, &self.interaction.token, message.id, &reply);
+                self.set_state(ResponseState::Responded);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Replies to a press on a button or menu. [`ReplySink::edit`] updates the message the component
+/// is on, [`ReplySink::send`] replies with a new message.
+pub struct ComponentSink {
+    http: Arc<Http>,
+    interaction: ComponentInteraction,
+    state: Mutex<ResponseState>,
+}
+
+impl ComponentSink {
+    pub fn new(http: Arc<Http>, interaction: ComponentInteraction) -> Self {
+        ComponentSink { http, interaction, state: Mutex::new(ResponseState::Pending) }
+    }
+
+    fn state(&self) -> ResponseState {
+        *self.state.lock().unwrap()
+    }
+
+    fn set_state(&self, state: ResponseState) {
+        *self.state.lock().unwrap() = state;
+    }
+}
+
+#[async_trait]
+impl ReplySink for ComponentSink {
+    /// Acknowledges the press without changing the message yet.
+    async fn defer(&self, _ephemeral: bool) -> serenity::Result<()> {
+        if self.state() != ResponseState::Pending {
+            return Ok(());
+        }
+
+        self.interaction.create_response(&self.http, CreateInteractionResponse::Acknowledge).await?;
+        self.set_state(ResponseState::Deferred);
+        Ok(())
+    }
+
+    async fn send(&self, reply: Reply) -> serenity::Result<()> {
+        match self.state() {
+            ResponseState::Pending => {
+                let response = CreateInteractionResponse::Message(reply.to_interaction_message());
+                self.interaction.create_response(&self.http, response).await?;
+            }
+            ResponseState::Deferred | ResponseState::Responded => {
+                self.interaction.create_followup(&self.http, reply.to_followup()).await?;
+            }
+        }
+        self.set_state(ResponseState::Responded);
+        Ok(())
+    }
+
+    async fn edit(&self, reply: Reply) -> serenity::Result<()> {
+        match self.state() {
+            ResponseState::Pending => {
+                let response = CreateInteractionResponse::UpdateMessage(reply.to_interaction_message());
+                self.interaction.create_response(&self.http, response).await?;
+            }
+            ResponseState::Deferred | ResponseState::Responded => {
+                self.interaction.edit_response(&self.http, reply.to_interaction_edit()).await?;
+            }
+        }
+        disable_response_on_expiry(&self.http, &self.interaction.token, self.interaction.message.id, &reply);
+        self.set_state(ResponseState::Responded);
+        Ok(())
+    }
+}
+
+/// Keeps replies in memory instead of sending them. The offline CLI prints them, and tests can
+/// inspect them.
+#[derive(Default)]
+pub struct CaptureSink {
+    replies: Mutex<Vec<Reply>>,
+}
+
+impl CaptureSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything sent so far, with edits applied.
+    pub fn replies(&self) -> Vec<Reply> {
+        self.replies.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl ReplySink for CaptureSink {
+    async fn defer(&self, _ephemeral: bool) -> serenity::Result<()> {
+        Ok(())
+    }
+
+    async fn send(&self, reply: Reply) -> serenity::Result<()> {
+        self.replies.lock().unwrap().push(reply);
+        Ok(())
+    }
+
+    async fn edit(&self, reply: Reply) -> serenity::Result<()> {
+        let mut replies = self.replies.lock().unwrap();
+        match replies.last_mut() {
+            Some(last) => *last = reply,
+            None => replies.push(reply),
+        }
+        Ok(())
+    }
+}
+
+/// One run of a command, however it was invoked: the arguments, where it was sent from and where
+/// its replies go.
+pub struct Invocation<'a> {
+    pub guild_id: Option<GuildId>,
+    /// The user who ran the command, or `None` in the offline CLI.
+    pub user_id: Option<UserId>,
+    pub input: &'a str,
+    /// The user's permission level, for commands that let everyone use some parts but only admins
+    /// others. `Everyone` unless the caller sets it with [`Invocation::level`].
+    pub level: PermissionLevel,
+    sink: &'a dyn ReplySink,
+}
+
+impl<'a> Invocation<'a> {
+    pub fn new(guild_id: Option<GuildId>, user_id: Option<UserId>, input: &'a str, sink: &'a dyn ReplySink) -> Self {
+        Invocation { guild_id, user_id, input, level: PermissionLevel::Everyone, sink }
+    }
+
+    pub fn level(mut self, level: PermissionLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Acknowledges the invocation. Commands call this before Riot lookups or other slow work, since
+    /// Discord drops interactions that get no response within 3 seconds.
+    pub async fn defer(&self, ephemeral: bool) {
+        if let Err(why) = self.sink.defer(ephemeral).await {
+            metrics::record_error("discord");
+            tracing::error!("Error deferring reply: {:?}", why);
+        }
+    }
+
+    pub async fn send(&self, reply: impl Into<Reply>) {
+        if let Err(why) = self.sink.send(reply.into()).await {
+            metrics::record_error("discord");
+            tracing::error!("Error sending reply: {:?}", why);
+        }
+    }
+
+    pub async fn edit(&self, reply: impl Into<Reply>) {
+        if let Err(why) = self.sink.edit(reply.into()).await {
+            metrics::record_error("discord");
+            tracing::error!("Error editing reply: {:?}", why);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::profile::ProfileCommand;
+    use crate::commands::Command;
+    use crate::riot;
+
+    #[tokio::test]
+    async fn captures_a_command_run_against_the_fixtures() {
+        riot::use_test_fixtures();
+
+        let sink = CaptureSink::new();
+        ProfileCommand::new().run(&Invocation::new(None, None, "Faker#KR1 --region kr", &sink)).await;
+
+        let replies = sink.replies();
+        assert_eq!(replies.len(), 1);
+        let text = replies[0].render_text();
+        assert!(text.contains("**Riot ID**: Faker#KR1 (KR)"), "{}", text);
+        assert!(text.contains("CHALLENGER: 1337 LP (Solo/Duo)"), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn edits_replace_the_last_capture() {
+        let sink = CaptureSink::new();
+        let invocation = Invocation::new(None, None, "", &sink);
+        invocation.edit("first").await;
+        invocation.send("second").await;
+        invocation.edit("third").await;
+
+        let replies: Vec<String> = sink.replies().iter().map(Reply::render_text).collect();
+        assert_eq!(replies, ["first", "third"]);
+    }
+}
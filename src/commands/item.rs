@@ -0,0 +1,120 @@
+use serenity::all::{CreateEmbed, CreateEmbedFooter};
+use serenity::async_trait;
+use std::time::Duration;
+
+use crate::commands::{Command, Invocation, Reply};
+use crate::ddragon::{self, models::Item, StaticData};
+use crate::utils;
+
+/// Discord's limit for an embed field value.
+const FIELD_MAX: usize = 1024;
+
+pub struct ItemCommand;
+
+impl ItemCommand {
+    pub fn new() -> Self {
+        ItemCommand
+    }
+}
+
+#[async_trait]
+impl Command for ItemCommand {
+    async fn run(&self, invocation: &Invocation<'_>) {
+        if invocation.input.trim().is_empty() {
+            invocation.send("Usage: `item <name>`, e.g. `item deathcap`.").await;
+            return;
+        }
+
+        invocation.defer(false).await;
+
+        let data = match ddragon::get().await {
+            Ok(data) => data,
+            Err(why) => {
+                tracing::warn!("Error loading Data Dragon: {:#}", why);
+                invocation.send("Item data is not available right now, try again later.").await;
+                return;
+            }
+        };
+
+        match data.find_item(invocation.input) {
+            Ok(item) => invocation.send(Reply::default().embed(render(&data, item))).await,
+            Err(why) => invocation.send(why.to_string()).await,
+        }
+    }
+
+    fn autocomplete(&self, input: &str) -> Vec<String> {
+        ddragon::cached().map(|data| data.suggest_items(input, 25)).unwrap_or_default()
+    }
+
+    fn autocompletes(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str {
+        "item"
+    }
+
+    fn description(&self) -> &str {
+        "Show an item's stats, cost, build path and passives."
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+}
+
+fn render(data: &StaticData, item: &Item) -> CreateEmbed {
+    let (stats, effects) = split_description(&item.description);
+
+    let mut embed = CreateEmbed::new().title(item.name.clone()).thumbnail(data.item_icon_url(item));
+    if !item.plaintext.is_empty() {
+        embed = embed.description(item.plaintext.clone());
+    }
+
+    let cost = if item.gold.purchasable {
+        let mut cost = format!("{} gold", item.gold.total);
+        if item.gold.base != item.gold.total {
+            cost.push_str(&format!(" ({} to combine)", item.gold.base));
+        }
+        format!("{}, sells for {}", cost, item.gold.sell)
+    } else {
+        "Not purchasable".to_string()
+    };
+    embed = embed.field("Cost", cost, false);
+
+    if !stats.is_empty() {
+        embed = embed.field("Stats", utils::truncate(&stats, FIELD_MAX), false);
+    }
+    if !effects.is_empty() {
+        embed = embed.field("Effects", utils::truncate(&effects, FIELD_MAX), false);
+    }
+
+    let names = |ids: &[String]| -> Vec<String> {
+        ids.iter().filter_map(|id| data.item_by_id(id)).map(|item| item.name.clone()).collect()
+    };
+    let from = names(&item.from);
+    if !from.is_empty() {
+        embed = embed.field("Builds from", utils::truncate(&from.join(" + "), FIELD_MAX), false);
+    }
+    let into = names(&item.into);
+    if !into.is_empty() {
+        embed = embed.field("Builds into", utils::truncate(&into.join(", "), FIELD_MAX), false);
+    }
+
+    embed.footer(CreateEmbedFooter::new(format!("Patch {}", data.version)))
+}
+
+/// Splits an item description into its `<stats>` block and the passives and actives after it, both
+/// as plain text.
+fn split_description(description: &str) -> (String, String) {
+    let Some(start) = description.find("<stats>") else {
+        return (String::new(), ddragon::plain_text(description));
+    };
+    let Some(end) = description[start..].find("</stats>").map(|end| start + end) else {
+        return (String::new(), ddragon::plain_text(description));
+    };
+
+    let stats = ddragon::plain_text(&description[start..end]);
+    let effects = ddragon::plain_text(&format!("{}{}", &description[..start], &description[end..]));
+    (stats, effects)
+}
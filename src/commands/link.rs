@@ -0,0 +1,96 @@
+use anyhow::Result;
+use riven::consts::PlatformRoute;
+use serenity::all::{GuildId, UserId};
+use serenity::async_trait;
+use std::time::Duration;
+
+use crate::commands::region::RegionCommand;
+use crate::commands::{Command, Invocation};
+use crate::riot::{self, player};
+use crate::settings::SETTINGS;
+
+const USAGE: &str = "Usage: `link <name#tag>` to link your Riot ID on this server, `link` to show it or `link remove` to unlink it.";
+
+pub struct LinkCommand;
+
+impl LinkCommand {
+    pub fn new() -> Self {
+        LinkCommand
+    }
+}
+
+#[async_trait]
+impl Command for LinkCommand {
+    async fn run(&self, invocation: &Invocation<'_>) {
+        let (Some(guild_id), Some(user_id)) = (invocation.guild_id, invocation.user_id) else {
+            invocation.send("Riot IDs are linked per server, so use `link` on a server.").await;
+            return;
+        };
+        let (input, platform) = match RegionCommand::resolve(invocation.guild_id, invocation.input) {
+            Ok(resolved) => resolved,
+            Err(reply) => {
+                invocation.send(reply).await;
+                return;
+            }
+        };
+
+        match input.trim() {
+            "" => {
+                let response = match SETTINGS.get(Some(guild_id)).linked_account(user_id) {
+                    Some(riot_id) => format!("Your Riot ID on this server is `{}`.", riot_id),
+                    None => format!("You have not linked a Riot ID on this server.\n{}", USAGE),
+                };
+                invocation.send(response).await;
+            }
+            "remove" => {
+                SETTINGS.update(guild_id, |settings| {
+                    settings.linked_accounts.remove(&user_id.get());
+                });
+                invocation.send("Your Riot ID is no longer linked on this server.").await;
+            }
+            riot_id if riot_id.contains('#') => {
+                invocation.defer(false).await;
+                let response = match link(guild_id, user_id, platform, riot_id).await {
+                    Ok(response) => response,
+                    Err(why) => {
+                        tracing::warn!("Error linking a Riot ID: {:#}", why);
+                        riot::error_reply(&why, "Failed to look up the Riot ID.")
+                    }
+                };
+                invocation.send(response).await;
+            }
+            _ => invocation.send(USAGE).await,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "link"
+    }
+
+    fn description(&self) -> &str {
+        "Link your Riot ID on this server, so commands such as inhouse accept a mention of you."
+    }
+
+    fn takes_region(&self) -> bool {
+        true
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+}
+
+/// Links the member to the account for `riot_id`, spelt as Riot does.
+async fn link(guild_id: GuildId, user_id: UserId, platform: PlatformRoute, riot_id: &str) -> Result<String> {
+    let (game_name, tag_line) = player::parse_riot_id(riot_id);
+    let Some(account) = player::account(riot::api()?, platform, game_name, tag_line).await? else {
+        return Ok(format!("There is no summoner named `{}`.", riot_id));
+    };
+
+    let riot_id = player::riot_id(&account);
+    let response = format!("Linked <@{}> to `{}`.", user_id, riot_id);
+    SETTINGS.update(guild_id, |settings| {
+        settings.linked_accounts.insert(user_id.get(), riot_id);
+    });
+    Ok(response)
+}
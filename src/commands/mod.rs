@@ -1,20 +1,151 @@
+use serenity::all::{CommandOptionType, CreateCommand, CreateCommandOption};
 use serenity::async_trait;
-use serenity::model::channel::Message;
-use serenity::model::application::Interaction;
-use serenity::prelude::*;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::permissions::PermissionLevel;
+use crate::riot;
+
+pub use components::{ComponentId, ComponentPress};
+pub use invocation::{Invocation, Reply};
 
 #[async_trait]
 pub trait Command: Send + Sync {
-    async fn handle(&self, ctx: &Context, msg: &Message, input: &str);
+    /// Runs the command. Replies go through `invocation`, so the same code serves prefix commands,
+    /// slash commands and the offline CLI.
+    async fn run(&self, invocation: &Invocation<'_>);
+
+    /// Handles a press on one of this command's buttons or menus, whose [`ComponentId`] names the
+    /// command. [`Invocation::edit`] updates the message the component is on.
+    async fn handle_component(&self, invocation: &Invocation<'_>, press: &ComponentPress) {
+        tracing::warn!(action = %press.id.action, "Command has no component handler");
+        invocation.send(Reply::text("This menu is no longer active.").ephemeral(true)).await;
+    }
+
+    /// Choices for the slash command's `input` option while the user is typing `input`. Discord
+    /// expects an answer within 3 seconds, so this must not wait on Riot or Data Dragon.
+    fn autocomplete(&self, _input: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Whether Discord should ask [`Command::autocomplete`] for choices.
+    fn autocompletes(&self) -> bool {
+        false
+    }
+
+    /// Whether the command looks something up in a region, so its slash command offers a `region`
+    /// option.
+    fn takes_region(&self) -> bool {
+        false
+    }
 
-    async fn handle_interaction(&self, ctx: &Context, interaction: &Interaction);
-    
     fn name(&self) -> &str;
     fn description(&self) -> &str;
+
+    fn permission(&self) -> PermissionLevel {
+        PermissionLevel::Everyone
+    }
+
+    /// Minimum time between two uses of this command by the same user.
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(2)
+    }
 }
 
+/// The slash command registered for `command`. Its arguments go in one `input` option, written as
+/// after the prefix, and commands that take a region get a `region` option with every region.
+pub fn slash_command(command: &dyn Command) -> CreateCommand {
+    let input = CreateCommandOption::new(CommandOptionType::String, "input", "Arguments, as after the prefix")
+        .set_autocomplete(command.autocompletes());
+    let mut slash = CreateCommand::new(command.name()).description(command.description()).add_option(input);
+
+    if command.takes_region() {
+        let option = CreateCommandOption::new(CommandOptionType::String, "region", "Region to use instead of the server's");
+        let option = riot::region::all().fold(option, |option, platform| {
+            option.add_string_choice(riot::region::label(platform), platform.to_string())
+        });
+        slash = slash.add_option(option);
+    }
+    slash
+}
+
+/// Every command not disabled in the configuration, by name so listings come out sorted. The
+/// handler, `help` and the CLI all work from this one list.
+pub fn all() -> BTreeMap<String, Box<dyn Command>> {
+    let commands: Vec<Box<dyn Command>> = vec![
+        Box::new(mastery::MasteryCommand::new()),
+        Box::new(about::AboutCommand::new()),
+        Box::new(help::HelpCommand::new()),
+        Box::new(profile::ProfileCommand::new()),
+        Box::new(region::RegionCommand::new()),
+        Box::new(config::ConfigCommand::new()),
+        Box::new(champion::ChampionCommand::new()),
+        Box::new(item::ItemCommand::new()),
+        Box::new(rune::RuneCommand::new()),
+        Box::new(rotation::RotationCommand::new()),
+        Box::new(status::StatusCommand::new()),
+        Box::new(compare::CompareCommand::new()),
+        Box::new(duo::DuoCommand::new()),
+        Box::new(champstats::ChampStatsCommand::new()),
+        Box::new(roles::RolesCommand::new()),
+        Box::new(inhouse::InhouseCommand::new()),
+        Box::new(link::LinkCommand::new()),
+    ];
+
+    let disabled = &crate::config::get().features.disabled_commands;
+    commands
+        .into_iter()
+        .map(|command| (command.name().to_string(), command))
+        .filter(|(name, _)| !disabled.contains(name))
+        .collect()
+}
+
+pub mod components;
 pub mod help;
+pub mod inhouse;
+pub mod invocation;
+pub mod item;
+pub mod link;
 pub mod mastery;
 pub mod about;
+pub mod champion;
+pub mod champstats;
+pub mod compare;
+pub mod duo;
 pub mod profile;
-pub mod region;
\ No newline at end of file
+pub mod region;
+pub mod roles;
+pub mod rotation;
+pub mod rune;
+pub mod status;
+pub mod config;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn options(command: &dyn Command) -> Vec<Value> {
+        let slash = serde_json::to_value(slash_command(command)).unwrap();
+        slash["options"].as_array().unwrap().clone()
+    }
+
+    #[test]
+    fn slash_commands_offer_autocomplete_and_region() {
+        let options = options(&champstats::ChampStatsCommand::new());
+        assert_eq!(options.len(), 2);
+        assert_eq!(options[0]["name"], "input");
+        assert_eq!(options[0]["autocomplete"], true);
+        assert_eq!(options[1]["name"], "region");
+        let choices = options[1]["choices"].as_array().unwrap();
+        assert!(choices.iter().any(|choice| choice["name"] == "South Korea (KR)" && choice["value"] == "KR"));
+        assert!(choices.len() <= 25);
+    }
+
+    #[test]
+    fn slash_commands_without_lookups_only_take_input() {
+        let options = options(&help::HelpCommand::new());
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0]["autocomplete"], false);
+    }
+}
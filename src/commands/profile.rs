@@ -1,13 +1,12 @@
 use anyhow::Result;
-use serenity::all::{CreateInteractionResponse, CreateInteractionResponseMessage, Interaction};
 use serenity::async_trait;
-use serenity::model::channel::Message;
-use serenity::prelude::*;
 use riven::{RiotApi, consts::PlatformRoute};
-use std::env;
+use std::time::Duration;
 
-use crate::commands::Command;
+use crate::commands::{Command, Invocation};
 use crate::commands::region::RegionCommand;
+use crate::ddragon;
+use crate::riot::{self, player};
 
 pub struct ProfileCommand;
 
@@ -19,65 +18,29 @@ impl ProfileCommand {
 
 #[async_trait]
 impl Command for ProfileCommand {
-    async fn handle(&self, ctx: &Context, msg: &Message, input: &str) {
-        let command_input = input.to_string();
-        let parts: Vec<&str> = command_input.trim().split('#').collect();
-        let game_name = parts[0];
-        let tag_line = if parts.len() > 1 { parts[1] } else { "" };
-        
-        let region = RegionCommand::get_region();
-
-        match get_summoner_stats(region, game_name, tag_line).await {
-            Ok(response) => {
-                if let Err(why) = msg.channel_id.say(&ctx.http, response).await {
-                    println!("Error sending message: {:?}", why);
-                }
+    async fn run(&self, invocation: &Invocation<'_>) {
+        let (input, region) = match RegionCommand::resolve(invocation.guild_id, invocation.input) {
+            Ok(resolved) => resolved,
+            Err(reply) => {
+                invocation.send(reply).await;
+                return;
             }
-            Err(why) => {
-                println!("Error fetching champion masteries: {:?}", why);
-                if let Err(why) = msg.channel_id.say(&ctx.http, "Failed to fetch champion masteries.".to_string()).await {
-                    println!("Error sending message: {:?}", why);
-                }
-            }
-        }
+        };
 
-    }
+        let (game_name, tag_line) = player::parse_riot_id(&input);
+
+        invocation.defer(false).await;
 
-    async fn handle_interaction(&self, ctx: &Context, interaction: &Interaction) {
-        if let Some(command_interaction) = interaction.as_command() {
-            let input = command_interaction.data.options.iter()
-                .find(|option| option.name == "input") // Предполагается, что вы передали параметр input
-                .map(|option| option.value.as_str().unwrap_or(""))
-                .unwrap_or("");
-    
-            let parts: Vec<&str> = input.trim().split('#').collect();
-            let game_name = parts[0];
-            let tag_line = if parts.len() > 1 { parts[1] } else { "" };
-            
-            let region = RegionCommand::get_region();
-    
-            match get_summoner_stats(region, game_name, tag_line).await {
-                Ok(response) => {
-                    let create_response = CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().content(response));
-    
-                    if let Err(why) = command_interaction.create_response(&ctx.http, create_response).await {
-                        tracing::error!("Error responding to interaction: {:?}", why);
-                    }
-                }
-                Err(why) => {
-                    println!("Error fetching champion masteries: {:?}", why);
-                    let error_response = CreateInteractionResponse::Message(CreateInteractionResponseMessage::default().content("Failed to fetch champion masteries.".to_string()));
-    
-                    if let Err(why) = command_interaction.create_response(&ctx.http, error_response).await {
-                        tracing::error!("Error responding to interaction: {:?}", why);
-                    }
-                }
+        let response = match get_summoner_stats(region, game_name, tag_line).await {
+            Ok(response) => response,
+            Err(why) => {
+                tracing::warn!("Error fetching summoner stats: {:#}", why);
+                riot::error_reply(&why, "Failed to fetch summoner profile.")
             }
-        } else {
-            println!("This interaction is not a command interaction.");
-        }
+        };
+        invocation.send(response).await;
     }
-    
+
     fn name(&self) -> &str {
         "profile"
     }
@@ -85,100 +48,137 @@ impl Command for ProfileCommand {
     fn description(&self) -> &str {
         "Show account and general statistics for the Disocrd user."
     }
+
+    fn takes_region(&self) -> bool {
+        true
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(10)
+    }
 }
 
 async fn get_summoner_stats(platform: PlatformRoute, game_name: &str, tag_line: &str) -> Result<String> {
-    let api_key = env::var("RIOT_API_KEY")?; 
-    let riot_api = RiotApi::new(&api_key);
-
+    let riot_api = riot::api()?;
 
-    debug_log(&format!("Fetching account for {}#{}", game_name, tag_line));
+    // Get the account by name and tag; everything else needs its PUUID
+    let Some(account) = player::account(riot_api, platform, game_name, tag_line).await? else {
+        return Ok("There is no summoner with that name.".to_string());
+    };
 
-    // Get the account by name and tag
-    let account = match riot_api.account_v1().get_by_riot_id(platform.to_regional(), game_name, tag_line).await {
-        Ok(account) => account.expect("There is no summoner with that name."),
+    tracing::debug!("Account details: {:?}, {:?}", account.game_name, account.tag_line);
+
+    let mut response = format!("\n**Riot ID**: {} ({})\n", player::riot_id(&account), platform.as_region_str());
+
+    tracing::debug!("Fetching profile sections for {}#{}", game_name, tag_line);
+
+    // The sections are independent, so fetch them concurrently and render whatever arrives
+    let (summary, masteries, recent, challenges) = futures::join!(
+        player::summary(riot_api, platform, &account.puuid),
+        player::masteries(riot_api, platform, &account.puuid),
+        player::recent_form(riot_api, platform, &account.puuid, RECENT_MATCHES),
+        fetch_challenges(riot_api, platform, &account.puuid),
+    );
+
+    let mut missing = Vec::new();
+
+    match summary {
+        Ok(summary) => {
+            let rank_info = if summary.ranks.is_empty() {
+                "Unranked".to_string()
+            } else {
+                summary
+                    .ranks
+                    .iter()
+                    .map(|rank| format!("{} ({})", rank.rank, rank.queue_name()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            response.push_str("**Summoner Statistics**:\n```");
+            response.push_str(&format!(
+                "{: <20}: {}\n\
+                 {: <20}: {}\n\
+                 {: <20}: {}\n\
+                 {: <20}: {}\n\
+                 {: <20}: {:.2}%\n",
+                "Summoner Level", summary.level,
+                "Rank", rank_info,
+                "Total Wins", summary.total_wins(),
+                "Total Losses", summary.total_losses(),
+                "Win Rate", summary.win_rate()
+            ));
+            response.push_str("```\n"); 
+        }
         Err(e) => {
-            println!("Error fetching account: {:?}", e);
-            return Ok("Failed to fetch account.".to_string());
+            tracing::warn!("Error fetching summoner statistics: {:#}", e);
+            missing.push("summoner statistics");
         }
-    };
-
-    debug_log(&format!("Account details: {:?}, {:?}", account.game_name, account.tag_line));
-
-    let mut response = format!("\n**Riot ID**: {}#{} ({})\n", 
-        account.game_name.unwrap_or_default(), 
-        account.tag_line.unwrap_or_default(),
-        platform.as_region_str());
-
-    debug_log(&format!("Fetching summoner profile for {}#{}", game_name, tag_line));
+    }
 
-    let summoner = match riot_api.summoner_v4().get_by_puuid(platform, &account.puuid).await {
-        Ok(summoner) => summoner,
+    match masteries {
+        Ok(masteries) if !masteries.is_empty() => {
+            let top: Vec<String> = masteries
+                .iter()
+                .take(3)
+                .map(|mastery| format!("{} ({})", ddragon::champion_name(mastery.champion_id), mastery.champion_points))
+                .collect();
+            response.push_str(&format!("**Top Champions**: {}\n", top.join(", ")));
+        }
+        Ok(_) => {}
         Err(e) => {
-            println!("Error fetching summoner profile: {:?}", e);
-            return Ok("Failed to fetch summoner profile".to_string());
+            tracing::warn!("Error fetching champion masteries: {:#}", e);
+            missing.push("champion masteries");
         }
-    };
-
-    // Get and add information
-    let (rank_info, total_wins, total_losses, win_rate) = extract_league_info(&riot_api, platform, &summoner.id).await?;
-
-    
-    response.push_str("**Summoner Statistics**:\n```");
-    response.push_str(&format!(
-        "{: <20}: {}\n\
-         {: <20}: {}\n\
-         {: <20}: {}\n\
-         {: <20}: {}\n\
-         {: <20}: {:.2}%\n",
-        "Summoner Level", summoner.summoner_level,
-        "Rank", rank_info,
-        "Total Wins", total_wins,
-        "Total Losses", total_losses,
-        "Win Rate", win_rate
-    ));
-
-    response.push_str("```\n"); 
-    Ok(response)
-}
+    }
 
-async fn extract_league_info(riot_api: &RiotApi, platform: PlatformRoute, summoner_id: &str) -> Result<(String, u32, u32, f64)> {
-    let leagues = match riot_api.league_v4().get_league_entries_for_summoner(platform, summoner_id).await {
-        Ok(leagues) => leagues,
+    match recent {
+        Ok(Some(form)) => {
+            response.push_str(&format!(
+                "**Recent Form** (last {} games): {}W {}L, {:.1}/{:.1}/{:.1} KDA\n",
+                form.games, form.wins, form.games - form.wins,
+                form.kills, form.deaths, form.assists
+            ));
+            if let Some(note) = &form.skipped_note {
+                response.push_str(&format!("_{}_\n", note));
+            }
+            if !form.roles.is_empty() {
+                let roles: Vec<String> = form.roles.iter().map(|stats| format!("{} {}", stats.role, stats.games)).collect();
+                response.push_str(&format!("**Roles**: {} ({})\n", roles.join(", "), player::main_role(&form.roles)));
+            }
+        }
+        Ok(None) => {}
         Err(e) => {
-            println!("Error fetching leagues: {:?}", e);
-            return Ok(("Unknown Rank".to_string(), 0, 0, 0.0));
+            tracing::warn!("Error fetching recent matches: {:#}", e);
+            missing.push("recent matches");
         }
-    };
-
-    let mut total_wins = 0;
-    let mut total_losses = 0;
-    let mut rank_info = String::new();
-
-    for entry in leagues {
-        total_wins += entry.wins;
-        total_losses += entry.losses;
+    }
 
-        rank_info.push_str(&format!(
-            "{tier} {rank}: {lp} LP",
-            tier = entry.tier.expect("No tier"),
-            rank = entry.rank.expect("Unranked"),
-            lp = entry.league_points,
-        ));
+    match challenges {
+        Ok(challenges) => response.push_str(&format!("**Challenges**: {}\n", challenges)),
+        Err(e) => {
+            tracing::warn!("Error fetching challenges: {:#}", e);
+            missing.push("challenges");
+        }
     }
 
-    let total_games = total_wins + total_losses;
-    let win_rate = if total_games > 0 {
-        (total_wins as f64 / total_games as f64 * 100.0).round()
-    } else {
-        0.0
-    };
+    if !missing.is_empty() {
+        response.push_str(&format!("_Could not load: {}._\n", missing.join(", ")));
+    }
 
-    Ok((rank_info, total_wins as u32, total_losses as u32, win_rate))
+    Ok(response)
 }
 
+/// Enough games for the role verdict to mean something; it needs at least 5 with a position, and
+/// ARAM and other modes without one do not count.
+const RECENT_MATCHES: i32 = 10;
 
+async fn fetch_challenges(riot_api: &RiotApi, platform: PlatformRoute, puuid: &str) -> Result<String> {
+    let player = riot::deferred(
+        platform,
+        "lol-challenges-v1.getPlayerData",
+        riot_api.lol_challenges_v1().get_player_data(platform, puuid),
+    )
+    .await?;
 
-fn debug_log(message: &str) {
-    println!("[DEBUG] {}", message);
-}
\ No newline at end of file
+    Ok(format!("{}, {} points", player.total_points.level, player.total_points.current))
+}
@@ -1,205 +1,132 @@
-use crate::commands::Command;
+use crate::commands::{Command, ComponentId, ComponentPress, Invocation, Reply};
+use crate::config;
+use crate::permissions::PermissionLevel;
+use crate::riot::region;
+use crate::settings::SETTINGS;
 use serenity::async_trait;
-use serenity::model::channel::Message;
-use serenity::builder::{CreateMessage, CreateSelectMenu, CreateSelectMenuOption, CreateActionRow};
-use serenity::prelude::*;
-use serenity::all::{ComponentInteractionDataKind, CreateInteractionResponse, CreateInteractionResponseMessage, Interaction, CreateSelectMenuKind};
+use serenity::builder::{CreateSelectMenu, CreateSelectMenuOption, CreateActionRow};
+use serenity::all::{GuildId, CreateSelectMenuKind};
 use riven::consts::PlatformRoute;
-use std::sync::Mutex;
-use lazy_static::lazy_static;
-
-fn debug_log(message: &str) {
-    println!("[DEBUG] {}", message);
-}
-
-lazy_static! {
-    static ref CURRENT_REGION: Mutex<PlatformRoute> = Mutex::new(PlatformRoute::RU); // Установите начальный регион по умолчанию
-}
 
 pub struct RegionCommand;
 
 impl RegionCommand {
     pub fn new() -> Self {
-        debug_log("Creating new RegionCommand instance.");
+        tracing::trace!("Creating new RegionCommand instance.");
         RegionCommand
     }
 
-    pub fn set_current_region(new_region: PlatformRoute) {
-        let mut region = CURRENT_REGION.lock().unwrap();
-        debug_log(&format!("Setting new current region: {:?}", new_region));
-        *region = new_region;
+    pub fn set_current_region(guild_id: GuildId, new_region: PlatformRoute) {
+        tracing::debug!("Setting new current region for {:?}: {:?}", guild_id, new_region);
+        SETTINGS.update(guild_id, |settings| settings.region = Some(new_region.to_string()));
     }
-    
-    pub fn get_region() -> PlatformRoute {
-        let region = CURRENT_REGION.lock().unwrap();
-        debug_log(&format!("Getting current region: {:?}", *region));
-        *region
+
+    /// The guild's region, or the configured default in DMs and guilds that have not set one.
+    pub fn get_region(guild_id: Option<GuildId>) -> PlatformRoute {
+        let region = SETTINGS
+            .get(guild_id)
+            .region()
+            .unwrap_or_else(|| config::get().riot.default_region());
+        tracing::trace!("Getting current region for {:?}: {:?}", guild_id, region);
+        region
     }
     pub fn region_options(&self) -> Vec<CreateSelectMenuOption> {
-        debug_log("Fetching region options.");
-        vec![
-            CreateSelectMenuOption::new("Brazil (BR1)", PlatformRoute::BR1.to_string()),
-            CreateSelectMenuOption::new("EUNE (EUN1)", PlatformRoute::EUN1.to_string()),
-            CreateSelectMenuOption::new("EUW (EUW1)", PlatformRoute::EUW1.to_string()),
-            CreateSelectMenuOption::new("Japan (JP1)", PlatformRoute::JP1.to_string()),
-            CreateSelectMenuOption::new("South Korea (KR)", PlatformRoute::KR.to_string()),
-            CreateSelectMenuOption::new("North America (NA1)", PlatformRoute::NA1.to_string()),
-            CreateSelectMenuOption::new("Oceania (OC1)", PlatformRoute::OC1.to_string()),
-            CreateSelectMenuOption::new("Russia (RU)", PlatformRoute::RU.to_string()),
-            CreateSelectMenuOption::new("Latin America (LA1)", PlatformRoute::LA1.to_string()),
-            CreateSelectMenuOption::new("Latin America (LA2)", PlatformRoute::LA2.to_string()),
-            CreateSelectMenuOption::new("Middle East (ME1)", PlatformRoute::ME1.to_string()),
-            CreateSelectMenuOption::new("Philippines (PH2)", PlatformRoute::PH2.to_string()),
-            CreateSelectMenuOption::new("Singapore (SG2)", PlatformRoute::SG2.to_string()),
-            CreateSelectMenuOption::new("Thailand (TH2)", PlatformRoute::TH2.to_string()),
-            CreateSelectMenuOption::new("Turkey (TR1)", PlatformRoute::TR1.to_string()),
-            CreateSelectMenuOption::new("Taiwan (TW2)", PlatformRoute::TW2.to_string()),
-            CreateSelectMenuOption::new("Vietnam (VN2)", PlatformRoute::VN2.to_string()),
-            CreateSelectMenuOption::new("PBE (PBE1)", PlatformRoute::PBE1.to_string()),
-        ]
+        tracing::trace!("Fetching region options.");
+        region::all()
+            .map(|platform| CreateSelectMenuOption::new(region::label(platform), platform.to_string()))
+            .collect()
     }
 
-    pub fn get_region_string() -> String {
-        let region = Self::get_region();
-        debug_log(&format!("Converting platform {:?} to string.", region));
-        match region {
-            PlatformRoute::BR1 => "Brazil (BR1)".to_string(),
-            PlatformRoute::EUN1 => "EUNE (EUN1)".to_string(),
-            PlatformRoute::EUW1 => "EUW (EUW1)".to_string(),
-            PlatformRoute::JP1 => "Japan (JP1)".to_string(),
-            PlatformRoute::KR => "South Korea (KR)".to_string(),
-            PlatformRoute::NA1 => "North America (NA1)".to_string(),
-            PlatformRoute::OC1 => "Oceania (OC1)".to_string(),
-            PlatformRoute::RU => "Russia (RU)".to_string(),
-            PlatformRoute::LA1 => "Latin America (LA1)".to_string(),
-            PlatformRoute::LA2 => "Latin America (LA2)".to_string(),
-            PlatformRoute::ME1 => "Middle East (ME1)".to_string(),
-            PlatformRoute::PH2 => "Philippines (PH2)".to_string(),
-            PlatformRoute::SG2 => "Singapore (SG2)".to_string(),
-            PlatformRoute::TH2 => "Thailand (TH2)".to_string(),
-            PlatformRoute::TR1 => "Turkey (TR1)".to_string(),
-            PlatformRoute::TW2 => "Taiwan (TW2)".to_string(),
-            PlatformRoute::VN2 => "Vietnam (VN2)".to_string(),
-            PlatformRoute::PBE1 => "PBE (PBE1)".to_string(),
-            _ => "Unknown region".to_string(),
+    pub fn get_region_string(guild_id: Option<GuildId>) -> String {
+        region::label(Self::get_region(guild_id))
+    }
+
+    /// The region to use for a Riot lookup, taking a `--region`/`region:` override off `input`.
+    /// Returns the rest of the input, or the reply to send if the override is not a region.
+    pub fn resolve(guild_id: Option<GuildId>, input: &str) -> Result<(String, PlatformRoute), String> {
+        match region::take_override(input) {
+            Ok((rest, platform)) => Ok((rest, platform.unwrap_or_else(|| Self::get_region(guild_id)))),
+            Err(why) => Err(why.to_string()),
         }
     }
+
+    /// The region select menu, with `id` as its `custom_id`.
+    fn region_menu(&self, id: &ComponentId) -> CreateActionRow {
+        let select_menu = CreateSelectMenu::new(
+            id.encode(),
+            CreateSelectMenuKind::String { options: self.region_options() }
+        )
+        .placeholder("Select a region");
+
+        CreateActionRow::SelectMenu(select_menu)
+    }
 }
 
 
 #[async_trait]
 impl Command for RegionCommand {
-    async fn handle(&self, ctx: &Context, msg: &Message, input: &str) {
-        debug_log(&format!("Handling region command with input: '{}'", input));
-
-      
-        let new_region = match input {
-            "BR1" => Some(PlatformRoute::BR1),
-            "EUN1" => Some(PlatformRoute::EUN1),
-            "EUW1" => Some(PlatformRoute::EUW1),
-            "JP1" => Some(PlatformRoute::JP1),
-            "KR" => Some(PlatformRoute::KR),
-            "NA1" => Some(PlatformRoute::NA1),
-            "OC1" => Some(PlatformRoute::OC1),
-            "RU" => Some(PlatformRoute::RU),
-            "LA1" => Some(PlatformRoute::LA1),
-            "LA2" => Some(PlatformRoute::LA2),
-            "ME1" => Some(PlatformRoute::ME1),
-            "PH2" => Some(PlatformRoute::PH2),
-            "SG2" => Some(PlatformRoute::SG2),
-            "TH2" => Some(PlatformRoute::TH2),
-            "TR1" => Some(PlatformRoute::TR1),
-            "TW2" => Some(PlatformRoute::TW2),
-            "VN2" => Some(PlatformRoute::VN2),
-            "PBE1" => Some(PlatformRoute::PBE1),
-            _ => None,
+    async fn run(&self, invocation: &Invocation<'_>) {
+        let input = invocation.input;
+        tracing::debug!("Handling region command with input: '{}'", input);
+
+        // DMs always use the configured default, lookups there can still pass `--region`
+        let Some(guild_id) = invocation.guild_id else {
+            let response = format!(
+                "📍 **Region in direct messages:** `{}`\nIt can only be changed on a server. Add `--region <code>` to a lookup to use another one.",
+                Self::get_region_string(None)
+            );
+            invocation.send(response).await;
+            return;
         };
 
-        
-
-        if let Some(region) = new_region {
-            Self::set_current_region(region);
-        } else {
-            debug_log("No valid region provided, keeping current region.");
+        // Anyone may look at the region, only admins may change it
+        let can_change = invocation.level >= PermissionLevel::Admin;
+        if !input.trim().is_empty() {
+            if !can_change {
+                invocation.send(Reply::text(PermissionLevel::Admin.denied_message()).ephemeral(true)).await;
+                return;
+            }
+            match region::parse(input) {
+                Ok(platform) => Self::set_current_region(guild_id, platform),
+                Err(why) => {
+                    invocation.send(why.to_string()).await;
+                    return;
+                }
+            }
         }
 
-        let current_region_str = Self::get_region_string();
+        let current_region_str = Self::get_region_string(invocation.guild_id);
         let response = format!("📍 **Current region:** `{}`", current_region_str);
 
-        debug_log(&format!("Response message: {}", response));
-
-        // Создаем меню выбора региона
-        let select_menu = CreateSelectMenu::new(
-            "region_select",
-            CreateSelectMenuKind::String { options: self.region_options() }
-        )
-        .placeholder("Select a region");
-
-        let action_row = CreateActionRow::SelectMenu(select_menu);
-
-        let builder = CreateMessage::new()
-            .content(response)
-            .components(vec![action_row]);
+        tracing::debug!("Response message: {}", response);
 
-        // Отправка сообщения
-        if let Err(why) = msg.channel_id.send_message(&ctx.http, builder).await {
-            println!("Error sending message: {:?}", why);
+        if can_change {
+            let menu = self.region_menu(&ComponentId::new(self.name(), "select", ""));
+            invocation.send(Reply::text(response).components(vec![menu])).await;
         } else {
-            debug_log("Message sent successfully.");
+            invocation.send(response).await;
         }
     }
 
-    async fn handle_interaction(&self, ctx: &Context, interaction: &Interaction) {
-        if let Some(component_interaction) = interaction.clone().message_component() {
-            if let ComponentInteractionDataKind::StringSelect { values, .. } = &component_interaction.data.kind {
-                if let Some(selected_value) = values.get(0) {
-                    let new_region = match selected_value.as_str() {
-                        "BR1" => Some(PlatformRoute::BR1),
-                        "EUN1" => Some(PlatformRoute::EUN1),
-                        "EUW1" => Some(PlatformRoute::EUW1),
-                        "JP1" => Some(PlatformRoute::JP1),
-                        "KR" => Some(PlatformRoute::KR),
-                        "NA1" => Some(PlatformRoute::NA1),
-                        "OC1" => Some(PlatformRoute::OC1),
-                        "RU" => Some(PlatformRoute::RU),
-                        "LA1" => Some(PlatformRoute::LA1),
-                        "LA2" => Some(PlatformRoute::LA2),
-                        "ME1" => Some(PlatformRoute::ME1),
-                        "PH2" => Some(PlatformRoute::PH2),
-                        "SG2" => Some(PlatformRoute::SG2),
-                        "TH2" => Some(PlatformRoute::TH2),
-                        "TR1" => Some(PlatformRoute::TR1),
-                        "TW2" => Some(PlatformRoute::TW2),
-                        "VN2" => Some(PlatformRoute::VN2),
-                        "PBE1" => Some(PlatformRoute::PBE1),
-                        _ => None,
-                    };
-
-                    
-
-                    if let Some(region) = new_region {
-                        RegionCommand::set_current_region(region);
-                    }
-
-                    let response_message = format!(
-                        "📍 **Current region:** `{}`",
-                        RegionCommand::get_region_string()
-                    );
-
-                    let create_response = CreateInteractionResponse::Message(
-                        CreateInteractionResponseMessage::default().content(&response_message)
-                    );
-
-                    if let Err(why) = component_interaction.create_response(&ctx.http, create_response).await {
-                        debug_log(&format!("Error responding to interaction: {:?}", why));
-                    }
+    async fn handle_component(&self, invocation: &Invocation<'_>, press: &ComponentPress) {
+        let (Some(guild_id), Some(selected)) = (invocation.guild_id, press.values.first()) else {
+            tracing::debug!("No region selected");
+            return;
+        };
+        // The menu is only shown to admins, but anyone can press it on their message
+        if invocation.level < PermissionLevel::Admin {
+            invocation.send(Reply::text(PermissionLevel::Admin.denied_message()).ephemeral(true)).await;
+            return;
+        }
 
-                    return;
-                }
-            }
+        match region::parse(selected) {
+            Ok(platform) => Self::set_current_region(guild_id, platform),
+            Err(_) => tracing::warn!("Unknown region in the select menu: {}", selected),
         }
 
-        debug_log("Interaction not handled or no value selected.");
+        // Keep the same id so the menu still expires with the original message
+        let response = format!("📍 **Current region:** `{}`", Self::get_region_string(invocation.guild_id));
+        invocation.edit(Reply::text(response).components(vec![self.region_menu(&press.id)])).await;
     }
 
     fn name(&self) -> &str {
@@ -207,6 +134,32 @@ impl Command for RegionCommand {
     }
 
     fn description(&self) -> &str {
-        "Show the current region and select a new one."
+        "Show the current region. Admins can select a new one."
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::invocation::CaptureSink;
+
+    #[tokio::test]
+    async fn anyone_can_view_the_region_but_only_admins_change_it() {
+        let guild_id = Some(GuildId::new(2621));
+        let command = RegionCommand::new();
+
+        let sink = CaptureSink::new();
+        command.run(&Invocation::new(guild_id, None, "", &sink)).await;
+        command.run(&Invocation::new(guild_id, None, "kr", &sink)).await;
+        let replies = sink.replies();
+        assert!(replies[0].render_text().contains("Current region"));
+        assert!(replies[0].components.is_empty());
+        assert_eq!(replies[1].render_text(), PermissionLevel::Admin.denied_message());
+        assert_eq!(RegionCommand::get_region(guild_id), config::get().riot.default_region());
+
+        let sink = CaptureSink::new();
+        command.run(&Invocation::new(guild_id, None, "kr", &sink).level(PermissionLevel::Admin)).await;
+        assert_eq!(RegionCommand::get_region(guild_id), PlatformRoute::KR);
+        assert_eq!(sink.replies()[0].components.len(), 1);
+    }
+}
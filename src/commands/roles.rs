@@ -0,0 +1,121 @@
+use anyhow::Result;
+use riven::consts::PlatformRoute;
+use serenity::all::CreateEmbed;
+use serenity::async_trait;
+use std::time::Duration;
+
+use crate::commands::region::RegionCommand;
+use crate::commands::{Command, Invocation, Reply};
+use crate::riot::matches::{self, Kind};
+use crate::riot::player;
+use crate::riot::{self, region};
+
+const USAGE: &str = "Usage: `roles <name#tag> [games]`, e.g. `roles Faker#KR1` or `roles Faker#KR1 50`";
+const DEFAULT_GAMES: i32 = 30;
+/// Width of the share bars, in blocks.
+const BAR_WIDTH: usize = 10;
+
+pub struct RolesCommand;
+
+impl RolesCommand {
+    pub fn new() -> Self {
+        RolesCommand
+    }
+}
+
+#[async_trait]
+impl Command for RolesCommand {
+    async fn run(&self, invocation: &Invocation<'_>) {
+        let (input, platform) = match RegionCommand::resolve(invocation.guild_id, invocation.input) {
+            Ok(resolved) => resolved,
+            Err(reply) => {
+                invocation.send(reply).await;
+                return;
+            }
+        };
+
+        let mut ids = player::split_riot_ids(&input);
+        let count = match ids.last().and_then(|last| last.parse::<i32>().ok()) {
+            Some(count) => {
+                ids.pop();
+                count.clamp(1, matches::MAX_COUNT)
+            }
+            None => DEFAULT_GAMES,
+        };
+        let [riot_id] = ids[..] else {
+            invocation.send(USAGE).await;
+            return;
+        };
+
+        invocation.defer(false).await;
+
+        let response = match run_roles(platform, riot_id, count).await {
+            Ok(response) => response,
+            Err(why) => {
+                tracing::warn!("Error fetching role statistics: {:#}", why);
+                riot::error_reply(&why, "Failed to fetch role statistics.").into()
+            }
+        };
+        invocation.send(response).await;
+    }
+
+    fn name(&self) -> &str {
+        "roles"
+    }
+
+    fn description(&self) -> &str {
+        "Show how often a player plays each position and how they do there."
+    }
+
+    fn takes_region(&self) -> bool {
+        true
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(15)
+    }
+}
+
+async fn run_roles(platform: PlatformRoute, riot_id: &str, count: i32) -> Result<Reply> {
+    let riot_api = riot::api()?;
+    let (game_name, tag_line) = player::parse_riot_id(riot_id);
+    let Some(account) = player::account(riot_api, platform, game_name, tag_line).await? else {
+        return Ok(format!("There is no summoner named `{}`.", riot_id).into());
+    };
+    let name = player::riot_id(&account);
+
+    let history = matches::recent(riot_api, platform, &account.puuid, Kind::Ranked, count).await?;
+    let roles = player::roles(history.games.iter().filter_map(|game| matches::participant(game, &account.puuid)));
+    if roles.is_empty() {
+        return Ok(format!("{} has no ranked games with positions in their match history.", name).into());
+    }
+
+    let total: usize = roles.iter().map(|stats| stats.games).sum();
+    let mut table = String::new();
+    for stats in &roles {
+        let share = stats.games as f64 / total as f64;
+        let filled = (share * BAR_WIDTH as f64).round() as usize;
+        table.push_str(&format!(
+            "{: <8}{}{} {: >3.0}%  {: >2} {: <5}  {: >3}% WR\n",
+            stats.role,
+            "█".repeat(filled),
+            "░".repeat(BAR_WIDTH - filled),
+            share * 100.0,
+            stats.games,
+            if stats.games == 1 { "game" } else { "games" },
+            stats.win_rate(),
+        ));
+    }
+
+    let embed = CreateEmbed::new()
+        .title(format!("Roles of {}", name))
+        .description(format!(
+            "{}\nLast {} ranked games{}\n```\n{}```",
+            region::label(platform),
+            history.games.len(),
+            history.skipped_note().map(|note| format!("\n_{}_", note)).unwrap_or_default(),
+            table
+        ))
+        .field("Verdict", player::main_role(&roles), false);
+    Ok(Reply::default().embed(embed))
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use riven::consts::{Champion, PlatformRoute};
+use riven::models::champion_v3::ChampionInfo;
+use serenity::all::{CreateEmbed, CreateEmbedFooter};
+use serenity::async_trait;
+use std::time::Duration;
+
+use crate::commands::region::RegionCommand;
+use crate::commands::{Command, Invocation, Reply};
+use crate::ddragon;
+use crate::riot::{self, region};
+
+pub struct RotationCommand;
+
+impl RotationCommand {
+    pub fn new() -> Self {
+        RotationCommand
+    }
+}
+
+#[async_trait]
+impl Command for RotationCommand {
+    async fn run(&self, invocation: &Invocation<'_>) {
+        let (_, platform) = match RegionCommand::resolve(invocation.guild_id, invocation.input) {
+            Ok(resolved) => resolved,
+            Err(reply) => {
+                invocation.send(reply).await;
+                return;
+            }
+        };
+
+        invocation.defer(false).await;
+
+        // Champion names come from Data Dragon when it is available, with riven's names as a fallback
+        if let Err(why) = ddragon::get().await {
+            tracing::warn!("Error loading Data Dragon: {:#}", why);
+        }
+
+        match fetch(platform).await {
+            Ok(rotation) => invocation.send(Reply::default().embed(render(platform, &rotation))).await,
+            Err(why) => {
+                tracing::warn!("Error fetching the free rotation: {:#}", why);
+                invocation.send(riot::error_reply(&why, "Failed to fetch the free champion rotation.")).await;
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "rotation"
+    }
+
+    fn description(&self) -> &str {
+        "Show this week's free champion rotation."
+    }
+
+    fn takes_region(&self) -> bool {
+        true
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+}
+
+/// This week's free rotation on `platform`.
+pub async fn fetch(platform: PlatformRoute) -> Result<ChampionInfo> {
+    let riot_api = riot::api()?;
+    riot::deferred(platform, "champion-v3.getChampionInfo", riot_api.champion_v3().get_champion_info(platform)).await
+}
+
+/// Champion names, sorted and comma separated.
+pub fn champion_list(champions: &[Champion]) -> String {
+    let mut names: Vec<String> = champions.iter().map(|champion| ddragon::champion_name(*champion)).collect();
+    names.sort();
+    if names.is_empty() {
+        "None".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+fn render(platform: PlatformRoute, rotation: &ChampionInfo) -> CreateEmbed {
+    CreateEmbed::new()
+        .title("Free champion rotation")
+        .description(region::label(platform))
+        .field(
+            format!("Free champions ({})", rotation.free_champion_ids.len()),
+            champion_list(&rotation.free_champion_ids),
+            false,
+        )
+        .field(
+            format!("New players (up to level {})", rotation.max_new_player_level),
+            champion_list(&rotation.free_champion_ids_for_new_players),
+            false,
+        )
+        .footer(CreateEmbedFooter::new("The rotation changes every Tuesday"))
+}
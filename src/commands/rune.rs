@@ -0,0 +1,76 @@
+use serenity::all::{CreateEmbed, CreateEmbedFooter};
+use serenity::async_trait;
+use std::time::Duration;
+
+use crate::commands::{Command, Invocation, Reply};
+use crate::ddragon::{self, models::{Rune, RuneTree}, StaticData};
+use crate::utils;
+
+pub struct RuneCommand;
+
+impl RuneCommand {
+    pub fn new() -> Self {
+        RuneCommand
+    }
+}
+
+#[async_trait]
+impl Command for RuneCommand {
+    async fn run(&self, invocation: &Invocation<'_>) {
+        if invocation.input.trim().is_empty() {
+            invocation.send("Usage: `rune <name>`, e.g. `rune electrocute`.").await;
+            return;
+        }
+
+        invocation.defer(false).await;
+
+        let data = match ddragon::get().await {
+            Ok(data) => data,
+            Err(why) => {
+                tracing::warn!("Error loading Data Dragon: {:#}", why);
+                invocation.send("Rune data is not available right now, try again later.").await;
+                return;
+            }
+        };
+
+        match data.find_rune(invocation.input) {
+            Ok((tree, slot, rune)) => invocation.send(Reply::default().embed(render(&data, tree, slot, rune))).await,
+            Err(why) => invocation.send(why.to_string()).await,
+        }
+    }
+
+    fn autocomplete(&self, input: &str) -> Vec<String> {
+        ddragon::cached().map(|data| data.suggest_runes(input, 25)).unwrap_or_default()
+    }
+
+    fn autocompletes(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str {
+        "rune"
+    }
+
+    fn description(&self) -> &str {
+        "Show a rune's tree, slot and description."
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+}
+
+fn render(data: &StaticData, tree: &RuneTree, slot: usize, rune: &Rune) -> CreateEmbed {
+    let slot = match slot {
+        0 => "Keystone".to_string(),
+        row => format!("Row {}", row),
+    };
+
+    CreateEmbed::new()
+        .title(rune.name.clone())
+        .thumbnail(data.rune_icon_url(&rune.icon))
+        .description(utils::truncate(&ddragon::plain_text(&rune.long_desc), 4000))
+        .field("Tree", tree.name.clone(), true)
+        .field("Slot", slot, true)
+        .footer(CreateEmbedFooter::new(format!("Patch {}", data.version)))
+}
@@ -0,0 +1,182 @@
+use anyhow::Result;
+use riven::consts::PlatformRoute;
+use riven::models::lol_status_v4::{Content, PlatformData, Status};
+use serenity::all::{CreateEmbed, CreateEmbedFooter};
+use serenity::async_trait;
+use std::time::Duration;
+
+use crate::commands::region::RegionCommand;
+use crate::commands::{Command, Invocation, Reply};
+use crate::riot::{self, region};
+use crate::settings::SETTINGS;
+use crate::utils;
+
+/// Discord allows 25 fields per embed.
+const MAX_ENTRIES: usize = 25;
+const FIELD_MAX: usize = 1024;
+
+pub struct StatusCommand;
+
+impl StatusCommand {
+    pub fn new() -> Self {
+        StatusCommand
+    }
+}
+
+#[async_trait]
+impl Command for StatusCommand {
+    async fn run(&self, invocation: &Invocation<'_>) {
+        let (_, platform) = match RegionCommand::resolve(invocation.guild_id, invocation.input) {
+            Ok(resolved) => resolved,
+            Err(reply) => {
+                invocation.send(reply).await;
+                return;
+            }
+        };
+
+        invocation.defer(false).await;
+
+        match fetch(platform).await {
+            Ok(data) => {
+                let language = SETTINGS.get(invocation.guild_id).language;
+                invocation.send(Reply::default().embed(render(platform, &data, &language))).await;
+            }
+            Err(why) => {
+                tracing::warn!("Error fetching the server status: {:#}", why);
+                invocation.send(riot::error_reply(&why, "Failed to fetch the server status.")).await;
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "status"
+    }
+
+    fn description(&self) -> &str {
+        "Show ongoing incidents and maintenances for the region."
+    }
+
+    fn takes_region(&self) -> bool {
+        true
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+}
+
+async fn fetch(platform: PlatformRoute) -> Result<PlatformData> {
+    let riot_api = riot::api()?;
+    riot::deferred(
+        platform,
+        "lol-status-v4.getPlatformData",
+        riot_api.lol_status_v4().get_platform_data(platform),
+    )
+    .await
+}
+
+fn render(platform: PlatformRoute, data: &PlatformData, language: &str) -> CreateEmbed {
+    let entries: Vec<&Status> = data.maintenances.iter().chain(&data.incidents).collect();
+
+    let mut embed = CreateEmbed::new().title(format!("Server status: {}", region::label(platform)));
+    if entries.is_empty() {
+        embed = embed.description("✅ No known issues.");
+    }
+    for status in entries.iter().take(MAX_ENTRIES) {
+        let (name, value) = describe(status, language);
+        embed = embed.field(name, value, false);
+    }
+    if entries.len() > MAX_ENTRIES {
+        embed = embed.footer(CreateEmbedFooter::new(format!("{} more not shown", entries.len() - MAX_ENTRIES)));
+    }
+    embed
+}
+
+/// The title and latest published update of an incident or maintenance, in `language` where Riot
+/// has a translation, as an embed field name and value.
+pub fn describe(status: &Status, language: &str) -> (String, String) {
+    let icon = match (status.maintenance_status.as_deref(), status.incident_severity.as_deref()) {
+        (Some(_), _) => "🛠️",
+        (None, Some("critical")) => "🔴",
+        (None, Some("warning")) => "⚠️",
+        _ => "ℹ️",
+    };
+    let title = translate(&status.titles, language).unwrap_or("Untitled");
+    let name = utils::truncate(&format!("{} {}", icon, title), 256);
+
+    let mut value = String::new();
+    if let Some(update) = latest_update(status) {
+        value.push_str(translate(&update.translations, language).unwrap_or_default());
+        value.push('\n');
+    }
+    let time = status.updated_at.as_deref().unwrap_or(&status.created_at);
+    value.push_str(&format!("_Updated {}_", format_time(time)));
+
+    (name, utils::truncate(&value, FIELD_MAX))
+}
+
+/// The newest published update, or `None` before Riot has posted one.
+pub fn latest_update(status: &Status) -> Option<&riven::models::lol_status_v4::Update> {
+    status.updates.iter().filter(|update| update.publish).max_by(|a, b| a.updated_at.cmp(&b.updated_at))
+}
+
+/// The text in `language` (`de` or `de_DE`), falling back to the same language in any country, then
+/// English, then whatever Riot sent first.
+fn translate<'a>(contents: &'a [Content], language: &str) -> Option<&'a str> {
+    let language = language.replace('-', "_");
+    let base = language.split('_').next().unwrap_or_default();
+    let base_of = |locale: &str| locale.split(['_', '-']).next().unwrap_or_default().to_string();
+
+    contents
+        .iter()
+        .find(|content| content.locale.replace('-', "_").eq_ignore_ascii_case(&language))
+        .or_else(|| contents.iter().find(|content| base_of(&content.locale).eq_ignore_ascii_case(base)))
+        .or_else(|| contents.iter().find(|content| content.locale.eq_ignore_ascii_case("en_US")))
+        .or_else(|| contents.first())
+        .map(|content| content.content.as_str())
+}
+
+/// `2024-10-15T10:04:12.000+00:00` as `2024-10-15 10:04 UTC`. Riot reports status times in UTC.
+fn format_time(time: &str) -> String {
+    match time.get(..16) {
+        Some(minutes) => format!("{} UTC", minutes.replace('T', " ")),
+        None => time.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(locales: &[&str]) -> Vec<Content> {
+        locales
+            .iter()
+            .map(|locale| Content { locale: locale.to_string(), content: format!("text in {}", locale) })
+            .collect()
+    }
+
+    #[test]
+    fn translates_to_the_closest_locale() {
+        let contents = contents(&["en_US", "de_DE", "de_AT", "fr_FR"]);
+
+        assert_eq!(translate(&contents, "de_AT"), Some("text in de_AT"));
+        assert_eq!(translate(&contents, "de-DE"), Some("text in de_DE"));
+        assert_eq!(translate(&contents, "de"), Some("text in de_DE"));
+        assert_eq!(translate(&contents, "DE_ch"), Some("text in de_DE"));
+        assert_eq!(translate(&contents, "ko_KR"), Some("text in en_US"));
+    }
+
+    #[test]
+    fn falls_back_to_the_first_translation_without_english() {
+        assert_eq!(translate(&contents(&["ko_KR", "ja_JP"]), "de"), Some("text in ko_KR"));
+        assert_eq!(translate(&[], "de"), None);
+    }
+
+    #[test]
+    fn formats_times_to_the_minute() {
+        assert_eq!(format_time("2024-10-15T10:04:12.000+00:00"), "2024-10-15 10:04 UTC");
+        assert_eq!(format_time("2024-10-15T10:04"), "2024-10-15 10:04 UTC");
+        assert_eq!(format_time("2024-10-15"), "2024-10-15");
+        assert_eq!(format_time(""), "");
+    }
+}
@@ -0,0 +1,493 @@
+use anyhow::{bail, Context as _, Result};
+use riven::consts::PlatformRoute;
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing_subscriber::EnvFilter;
+
+use crate::cooldown::Limit;
+use crate::ddragon;
+use crate::riot::region;
+
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Bot configuration, read from a TOML file and then overridden by environment variables.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub discord: DiscordConfig,
+    pub riot: RiotConfig,
+    pub cooldowns: CooldownConfig,
+    pub cache: CacheConfig,
+    pub features: FeatureConfig,
+    pub logging: LoggingConfig,
+    pub metrics: MetricsConfig,
+    pub sharding: ShardingConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiscordConfig {
+    pub token: String,
+    /// Prefixes used by guilds that have not configured their own.
+    pub prefixes: Vec<String>,
+    /// Users with the `Owner` permission level, exempt from cooldowns.
+    pub owner_ids: Vec<u64>,
+}
+
+impl Default for DiscordConfig {
+    fn default() -> Self {
+        DiscordConfig {
+            token: String::new(),
+            prefixes: vec![crate::handler::DEFAULT_PREFIX.to_string()],
+            owner_ids: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiotConfig {
+    pub api_key: String,
+    /// Region used in DMs and in guilds that have not configured one.
+    pub default_region: String,
+    /// Application rate limits in Riot's `X-App-Rate-Limit` format.
+    pub app_rate_limit: String,
+    /// Directory of recorded responses served instead of the real Riot API, for development.
+    pub fixtures: Option<String>,
+}
+
+impl Default for RiotConfig {
+    fn default() -> Self {
+        RiotConfig {
+            api_key: String::new(),
+            default_region: "RU".to_string(),
+            app_rate_limit: "20:1,100:120".to_string(),
+            fixtures: None,
+        }
+    }
+}
+
+impl RiotConfig {
+    pub fn default_region(&self) -> PlatformRoute {
+        region::parse(&self.default_region).unwrap_or(PlatformRoute::RU)
+    }
+}
+
+/// Shared command limits as `<uses>/<seconds>`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CooldownConfig {
+    pub user: String,
+    pub guild: String,
+    pub global: String,
+}
+
+impl Default for CooldownConfig {
+    fn default() -> Self {
+        CooldownConfig {
+            user: "5/30".to_string(),
+            guild: "20/60".to_string(),
+            global: "60/60".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub match_ttl_secs: u64,
+    /// How many match details to keep in memory, roughly 30 KB each.
+    pub match_cache_entries: usize,
+    /// How often to check for a new Data Dragon version.
+    pub static_data_ttl_secs: u64,
+    /// Where downloaded Data Dragon files are kept, one directory per version.
+    pub static_data_dir: String,
+    pub static_data_language: String,
+    /// Load Data Dragon from this directory and never download, e.g. for offline runs.
+    pub static_data_local: Option<String>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            match_ttl_secs: 24 * 60 * 60,
+            match_cache_entries: 500,
+            static_data_ttl_secs: 6 * 60 * 60,
+            static_data_dir: "ddragon".to_string(),
+            static_data_language: "en_US".to_string(),
+            static_data_local: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureConfig {
+    /// Commands that are not registered at all, in any guild.
+    pub disabled_commands: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// `tracing` filter directive, e.g. `info` or `lol_discord_bot=debug`. `RUST_LOG` takes precedence.
+    pub level: String,
+    /// `text` for humans or `json` for one JSON object per line.
+    pub format: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig { level: "info".to_string(), format: "text".to_string() }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// Serve `/metrics` and `/healthz` over HTTP.
+    pub enabled: bool,
+    pub listen: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig { enabled: false, listen: "0.0.0.0:9100".to_string() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShardingMode {
+    /// One shard, enough for up to 2500 guilds.
+    #[default]
+    Single,
+    /// As many shards as Discord recommends, all in this process.
+    Auto,
+    /// Shards `first..=last` of `total`, so several processes can split the shards between them.
+    Range,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShardingConfig {
+    pub mode: ShardingMode,
+    pub total: u32,
+    pub first: u32,
+    pub last: u32,
+}
+
+impl Default for ShardingConfig {
+    fn default() -> Self {
+        ShardingConfig { mode: ShardingMode::Single, total: 1, first: 0, last: 0 }
+    }
+}
+
+impl ShardingConfig {
+    /// Parses `SHARDS`: either `auto` or `<first>-<last>/<total>`, e.g. `0-3/8`.
+    fn parse_env(&mut self, value: &str) -> Result<()> {
+        if value.eq_ignore_ascii_case("auto") {
+            self.mode = ShardingMode::Auto;
+            return Ok(());
+        }
+
+        let parse = || -> Option<(u32, u32, u32)> {
+            let (range, total) = value.split_once('/')?;
+            let (first, last) = range.split_once('-')?;
+            Some((first.trim().parse().ok()?, last.trim().parse().ok()?, total.trim().parse().ok()?))
+        };
+        let (first, last, total) =
+            parse().with_context(|| format!("SHARDS: `{}` is not `auto` or `<first>-<last>/<total>`", value))?;
+
+        self.mode = ShardingMode::Range;
+        self.first = first;
+        self.last = last;
+        self.total = total;
+        Ok(())
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from).collect()
+}
+
+impl Config {
+    /// Reads `path` if it exists and applies environment overrides on top.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut config = if path.exists() {
+            let contents = fs::read_to_string(path).context("could not read the file")?;
+            toml::from_str(&contents)?
+        } else {
+            Config::default()
+        };
+
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    fn apply_env(&mut self) -> Result<()> {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+
+        if let Some(token) = var("DISCORD_TOKEN") {
+            self.discord.token = token;
+        }
+        if let Some(prefixes) = var("BOT_PREFIXES") {
+            self.discord.prefixes = split_list(&prefixes);
+        }
+        if let Some(owner_ids) = var("OWNER_IDS") {
+            self.discord.owner_ids = split_list(&owner_ids)
+                .iter()
+                .map(|id| id.parse().with_context(|| format!("OWNER_IDS: `{}` is not a user id", id)))
+                .collect::<Result<_>>()?;
+        }
+        if let Some(api_key) = var("RIOT_API_KEY") {
+            self.riot.api_key = api_key;
+        }
+        if let Some(region) = var("DEFAULT_REGION") {
+            self.riot.default_region = region;
+        }
+        if let Some(limit) = var("RIOT_APP_RATE_LIMIT") {
+            self.riot.app_rate_limit = limit;
+        }
+        if let Some(fixtures) = var("RIOT_FIXTURES") {
+            self.riot.fixtures = Some(fixtures);
+        }
+        if let Some(limit) = var("COOLDOWN_USER") {
+            self.cooldowns.user = limit;
+        }
+        if let Some(limit) = var("COOLDOWN_GUILD") {
+            self.cooldowns.guild = limit;
+        }
+        if let Some(limit) = var("COOLDOWN_GLOBAL") {
+            self.cooldowns.global = limit;
+        }
+        if let Some(ttl) = var("MATCH_CACHE_TTL") {
+            self.cache.match_ttl_secs = ttl.parse().with_context(|| format!("MATCH_CACHE_TTL: `{}` is not a number", ttl))?;
+        }
+        if let Some(entries) = var("MATCH_CACHE_ENTRIES") {
+            self.cache.match_cache_entries =
+                entries.parse().with_context(|| format!("MATCH_CACHE_ENTRIES: `{}` is not a number", entries))?;
+        }
+        if let Some(ttl) = var("STATIC_DATA_TTL") {
+            self.cache.static_data_ttl_secs = ttl.parse().with_context(|| format!("STATIC_DATA_TTL: `{}` is not a number", ttl))?;
+        }
+        if let Some(dir) = var("STATIC_DATA_DIR") {
+            self.cache.static_data_dir = dir;
+        }
+        if let Some(language) = var("STATIC_DATA_LANGUAGE") {
+            self.cache.static_data_language = language;
+        }
+        if let Some(dir) = var("STATIC_DATA_LOCAL") {
+            self.cache.static_data_local = Some(dir);
+        }
+        if let Some(commands) = var("DISABLED_COMMANDS") {
+            self.features.disabled_commands = split_list(&commands);
+        }
+        if let Some(level) = var("LOG_LEVEL") {
+            self.logging.level = level;
+        }
+        if let Some(format) = var("LOG_FORMAT") {
+            self.logging.format = format;
+        }
+        if let Some(shards) = var("SHARDS") {
+            self.sharding.parse_env(&shards)?;
+        }
+        if let Some(listen) = var("METRICS_LISTEN") {
+            self.metrics.enabled = true;
+            self.metrics.listen = listen;
+        }
+
+        Ok(())
+    }
+
+    /// Checks every value and reports all problems at once, one per line.
+    pub fn validate(&self) -> Result<()> {
+        self.check(false)
+    }
+
+    /// Like [`Config::validate`], for the offline CLI which never connects to Discord.
+    pub fn validate_offline(&self) -> Result<()> {
+        self.check(true)
+    }
+
+    fn check(&self, offline: bool) -> Result<()> {
+        let mut errors = Vec::new();
+
+        if !offline && self.discord.token.trim().is_empty() {
+            errors.push("discord.token is missing (set it in the config file or DISCORD_TOKEN)".to_string());
+        }
+        if self.discord.prefixes.is_empty() {
+            errors.push("discord.prefixes must contain at least one prefix".to_string());
+        }
+        if self.discord.prefixes.iter().any(|prefix| prefix.trim().is_empty()) {
+            errors.push("discord.prefixes must not contain empty prefixes".to_string());
+        }
+        if self.riot.api_key.trim().is_empty() && self.riot.fixtures.is_none() {
+            errors.push("riot.api_key is missing (set it in the config file or RIOT_API_KEY)".to_string());
+        }
+        if let Err(why) = region::parse(&self.riot.default_region) {
+            errors.push(format!("riot.default_region: {}", why));
+        }
+        let app_limits = crate::riot::queue::parse_limits(&self.riot.app_rate_limit);
+        if app_limits.is_empty() || app_limits.len() != self.riot.app_rate_limit.split(',').count() {
+            errors.push(format!(
+                "riot.app_rate_limit: `{}` is not in the `<requests>:<seconds>,...` format",
+                self.riot.app_rate_limit
+            ));
+        }
+        for (name, value) in [
+            ("cooldowns.user", &self.cooldowns.user),
+            ("cooldowns.guild", &self.cooldowns.guild),
+            ("cooldowns.global", &self.cooldowns.global),
+        ] {
+            if Limit::parse(value).is_none() {
+                errors.push(format!("{}: `{}` is not in the `<uses>/<seconds>` format with both above 0", name, value));
+            }
+        }
+        if self.cache.match_ttl_secs == 0 {
+            errors.push("cache.match_ttl_secs must be greater than 0".to_string());
+        }
+        if self.cache.match_cache_entries == 0 {
+            errors.push("cache.match_cache_entries must be greater than 0".to_string());
+        }
+        if self.cache.static_data_ttl_secs == 0 {
+            errors.push("cache.static_data_ttl_secs must be greater than 0".to_string());
+        }
+        if let Some(local) = &self.cache.static_data_local {
+            if !Path::new(local).join(ddragon::CHAMPIONS_FILE).is_file() {
+                errors.push(format!("cache.static_data_local: `{}` has no {}", local, ddragon::CHAMPIONS_FILE));
+            }
+        }
+        if self.features.disabled_commands.iter().any(|name| name == "config") {
+            errors.push("features.disabled_commands: the `config` command cannot be disabled".to_string());
+        }
+        if let Err(why) = EnvFilter::try_new(&self.logging.level) {
+            errors.push(format!("logging.level: `{}` is not a valid filter: {}", self.logging.level, why));
+        }
+        if !matches!(self.logging.format.as_str(), "text" | "json") {
+            errors.push(format!("logging.format: expected `text` or `json`, got `{}`", self.logging.format));
+        }
+        if self.sharding.mode == ShardingMode::Range {
+            let ShardingConfig { first, last, total, .. } = self.sharding;
+            if total == 0 {
+                errors.push("sharding.total must be greater than 0".to_string());
+            } else if first > last || last >= total {
+                errors.push(format!(
+                    "sharding: shards {}-{} are not a range within 0-{} (sharding.total = {})",
+                    first,
+                    last,
+                    total - 1,
+                    total
+                ));
+            }
+        }
+        if let Some(fixtures) = &self.riot.fixtures {
+            if !Path::new(fixtures).is_dir() {
+                errors.push(format!("riot.fixtures: `{}` is not a directory", fixtures));
+            }
+        }
+        if self.metrics.enabled && self.metrics.listen.parse::<SocketAddr>().is_err() {
+            errors.push(format!("metrics.listen: `{}` is not an address like `0.0.0.0:9100`", self.metrics.listen));
+        }
+
+        if !errors.is_empty() {
+            bail!("invalid configuration:\n  {}", errors.join("\n  "));
+        }
+        Ok(())
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Installs the configuration loaded at startup. Later calls are ignored.
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+/// The configuration loaded at startup, or the defaults if none was installed.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A configuration that passes the offline checks.
+    fn valid() -> Config {
+        let mut config = Config::default();
+        config.riot.api_key = "RGAPI-test".to_string();
+        config
+    }
+
+    fn errors(config: &Config) -> String {
+        config.validate_offline().map_or_else(|why| why.to_string(), |_| String::new())
+    }
+
+    #[test]
+    fn defaults_with_credentials_are_valid() {
+        assert!(valid().validate_offline().is_ok());
+        assert!(valid().validate().unwrap_err().to_string().contains("discord.token is missing"));
+    }
+
+    #[test]
+    fn rejects_invalid_limits() {
+        let mut config = valid();
+        config.cooldowns.user = "0/60".to_string();
+        config.cooldowns.guild = "5/0".to_string();
+        config.cooldowns.global = "often".to_string();
+        config.riot.app_rate_limit = "20:1,lots".to_string();
+        config.cache.match_cache_entries = 0;
+
+        let errors = errors(&config);
+        assert!(errors.contains("cooldowns.user: `0/60`"), "{}", errors);
+        assert!(errors.contains("cooldowns.guild: `5/0`"), "{}", errors);
+        assert!(errors.contains("cooldowns.global: `often`"), "{}", errors);
+        assert!(errors.contains("riot.app_rate_limit: `20:1,lots`"), "{}", errors);
+        assert!(errors.contains("cache.match_cache_entries"), "{}", errors);
+    }
+
+    #[test]
+    fn rejects_unknown_regions() {
+        let mut config = valid();
+        config.riot.default_region = "atlantis".to_string();
+        assert!(errors(&config).contains("riot.default_region"));
+
+        config.riot.default_region = "euw".to_string();
+        assert!(config.validate_offline().is_ok());
+        assert_eq!(config.riot.default_region(), PlatformRoute::EUW1);
+    }
+
+    /// The only test that touches the environment, since tests share it.
+    #[test]
+    fn environment_overrides_the_file() {
+        let dir = std::env::temp_dir().join(format!("config-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let file = "[riot]\napi_key = \"from-file\"\ndefault_region = \"KR\"\n[cooldowns]\nuser = \"1/10\"\n";
+        fs::write(&path, file).unwrap();
+
+        env::set_var("RIOT_API_KEY", "from-env");
+        env::set_var("COOLDOWN_USER", "");
+        env::set_var("SHARDS", "0-3/8");
+        let loaded = Config::load(&path);
+        env::set_var("MATCH_CACHE_TTL", "soon");
+        let invalid = Config::load(&path);
+        for name in ["RIOT_API_KEY", "COOLDOWN_USER", "SHARDS", "MATCH_CACHE_TTL"] {
+            env::remove_var(name);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        let config = loaded.unwrap();
+        assert_eq!(config.riot.api_key, "from-env");
+        assert_eq!(config.riot.default_region, "KR");
+        // Empty variables leave the file's value alone
+        assert_eq!(config.cooldowns.user, "1/10");
+        assert_eq!((config.sharding.mode, config.sharding.first, config.sharding.last), (ShardingMode::Range, 0, 3));
+        assert!(invalid.unwrap_err().to_string().contains("MATCH_CACHE_TTL: `soon`"));
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::CooldownConfig;
+
+/// Allows `capacity` uses, refilling one use every `period / capacity`.
+#[derive(Debug, Clone, Copy)]
+pub struct Limit {
+    pub capacity: u32,
+    pub period: Duration,
+}
+
+impl Limit {
+    pub fn new(capacity: u32, period: Duration) -> Self {
+        Limit { capacity, period }
+    }
+
+    /// Parses `<capacity>/<seconds>`, e.g. `5/60`. Both must be above 0, since an empty bucket
+    /// would block every command for good.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (capacity, seconds) = value.split_once('/')?;
+        let capacity: u32 = capacity.trim().parse().ok()?;
+        let seconds: u64 = seconds.trim().parse().ok()?;
+        (capacity > 0 && seconds > 0).then(|| Limit::new(capacity, Duration::from_secs(seconds)))
+    }
+
+    fn refill_interval(&self) -> Duration {
+        self.period / self.capacity
+    }
+}
+
+/// Limits shared by all commands, on top of each command's own cooldown.
+#[derive(Debug, Clone, Copy)]
+pub struct CooldownLimits {
+    pub user: Limit,
+    pub guild: Limit,
+    pub global: Limit,
+}
+
+impl Default for CooldownLimits {
+    fn default() -> Self {
+        CooldownLimits {
+            user: Limit::new(5, Duration::from_secs(30)),
+            guild: Limit::new(20, Duration::from_secs(60)),
+            global: Limit::new(60, Duration::from_secs(60)),
+        }
+    }
+}
+
+impl CooldownLimits {
+    /// Builds the limits from the `[cooldowns]` config, keeping the defaults for malformed values.
+    pub fn from_config(config: &CooldownConfig) -> Self {
+        let defaults = Self::default();
+        let read = |value: &str, default: Limit| Limit::parse(value).unwrap_or(default);
+
+        CooldownLimits {
+            user: read(&config.user, defaults.user),
+            guild: read(&config.guild, defaults.guild),
+            global: read(&config.global, defaults.global),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum BucketKey {
+    Command(String, u64),
+    User(u64),
+    Guild(u64),
+    Global,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: Limit, now: Instant) -> Self {
+        Bucket { tokens: limit.capacity as f64, updated: now }
+    }
+
+    fn refill(&mut self, limit: Limit, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        let per_token = limit.refill_interval().as_secs_f64();
+        if per_token > 0.0 {
+            self.tokens = (self.tokens + elapsed / per_token).min(limit.capacity as f64);
+        } else {
+            self.tokens = limit.capacity as f64;
+        }
+        self.updated = now;
+    }
+
+    /// Time until one token is available.
+    fn wait(&self, limit: Limit) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            limit.refill_interval().mul_f64(1.0 - self.tokens)
+        }
+    }
+}
+
+pub struct Cooldowns {
+    limits: CooldownLimits,
+    buckets: Mutex<HashMap<BucketKey, Bucket>>,
+}
+
+impl Cooldowns {
+    pub fn new(limits: CooldownLimits) -> Self {
+        Cooldowns { limits, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    /// Takes one use from every bucket that applies to the invocation. If any bucket is empty nothing
+    /// is taken and the time until all of them allow another use is returned.
+    pub fn try_acquire(&self, command: &str, cooldown: Duration, user_id: u64, guild_id: Option<u64>) -> Result<(), Duration> {
+        self.try_acquire_at(command, cooldown, user_id, guild_id, Instant::now())
+    }
+
+    fn try_acquire_at(
+        &self,
+        command: &str,
+        cooldown: Duration,
+        user_id: u64,
+        guild_id: Option<u64>,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let mut checks = vec![
+            (BucketKey::Command(command.to_string(), user_id), Limit::new(1, cooldown)),
+            (BucketKey::User(user_id), self.limits.user),
+            (BucketKey::Global, self.limits.global),
+        ];
+        if let Some(guild_id) = guild_id {
+            checks.push((BucketKey::Guild(guild_id), self.limits.guild));
+        }
+
+        let mut buckets = self.buckets.lock().unwrap();
+
+        let mut wait = Duration::ZERO;
+        for (key, limit) in &checks {
+            let bucket = buckets.entry(key.clone()).or_insert_with(|| Bucket::full(*limit, now));
+            bucket.refill(*limit, now);
+            wait = wait.max(bucket.wait(*limit));
+        }
+
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+
+        for (key, _) in &checks {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+
+        // Forget buckets that have been idle long enough to be full again
+        if buckets.len() > 10_000 {
+            let idle = self.limits.user.period.max(self.limits.guild.period).max(Duration::from_secs(600));
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated) < idle);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(user: Limit) -> CooldownLimits {
+        CooldownLimits { user, ..CooldownLimits::default() }
+    }
+
+    #[test]
+    fn parses_limits() {
+        let limit = Limit::parse(" 5 / 60 ").unwrap();
+        assert_eq!(limit.capacity, 5);
+        assert_eq!(limit.period, Duration::from_secs(60));
+        assert!(Limit::parse("5").is_none());
+        assert!(Limit::parse("five/60").is_none());
+        assert!(Limit::parse("0/60").is_none());
+        assert!(Limit::parse("5/0").is_none());
+    }
+
+    #[test]
+    fn malformed_config_keeps_defaults() {
+        let config = CooldownConfig { user: "2/10".to_string(), guild: "lots".to_string(), global: String::new() };
+        let limits = CooldownLimits::from_config(&config);
+        let defaults = CooldownLimits::default();
+
+        assert_eq!((limits.user.capacity, limits.user.period), (2, Duration::from_secs(10)));
+        assert_eq!((limits.guild.capacity, limits.guild.period), (defaults.guild.capacity, defaults.guild.period));
+        assert_eq!((limits.global.capacity, limits.global.period), (defaults.global.capacity, defaults.global.period));
+    }
+
+    #[test]
+    fn command_cooldown_refills_after_its_period() {
+        let cooldowns = Cooldowns::new(CooldownLimits::default());
+        let now = Instant::now();
+        let cooldown = Duration::from_secs(10);
+
+        assert!(cooldowns.try_acquire_at("profile", cooldown, 1, None, now).is_ok());
+        let wait = cooldowns.try_acquire_at("profile", cooldown, 1, None, now).unwrap_err();
+        assert_eq!(wait, cooldown);
+
+        let halfway = now + Duration::from_secs(5);
+        assert_eq!(cooldowns.try_acquire_at("profile", cooldown, 1, None, halfway).unwrap_err(), Duration::from_secs(5));
+        assert!(cooldowns.try_acquire_at("profile", cooldown, 1, None, now + cooldown).is_ok());
+        // Other users have their own cooldown
+        assert!(cooldowns.try_acquire_at("profile", cooldown, 2, None, now).is_ok());
+    }
+
+    #[test]
+    fn user_limit_refills_one_use_at_a_time() {
+        let cooldowns = Cooldowns::new(limits(Limit::new(2, Duration::from_secs(60))));
+        let now = Instant::now();
+
+        assert!(cooldowns.try_acquire_at("a", Duration::ZERO, 1, None, now).is_ok());
+        assert!(cooldowns.try_acquire_at("b", Duration::ZERO, 1, None, now).is_ok());
+        assert_eq!(cooldowns.try_acquire_at("c", Duration::ZERO, 1, None, now).unwrap_err(), Duration::from_secs(30));
+        assert!(cooldowns.try_acquire_at("c", Duration::ZERO, 1, None, now + Duration::from_secs(30)).is_ok());
+        assert!(cooldowns.try_acquire_at("c", Duration::ZERO, 1, None, now + Duration::from_secs(30)).is_err());
+    }
+
+    #[test]
+    fn denied_invocations_take_nothing() {
+        let cooldowns = Cooldowns::new(limits(Limit::new(1, Duration::from_secs(60))));
+        let now = Instant::now();
+        let cooldown = Duration::from_secs(10);
+
+        assert!(cooldowns.try_acquire_at("a", Duration::ZERO, 1, None, now).is_ok());
+        // The user limit is exhausted, so `b` must not use up its own command cooldown
+        assert!(cooldowns.try_acquire_at("b", cooldown, 1, None, now).is_err());
+        assert!(cooldowns.try_acquire_at("b", cooldown, 1, None, now + Duration::from_secs(60)).is_ok());
+    }
+}
@@ -0,0 +1,391 @@
+//! Static game data from Data Dragon: champions, items, runes, summoner spells and profile icons.
+//!
+//! Each version's JSON files are downloaded once into `cache.static_data_dir/<version>_<language>/`,
+//! e.g. `ddragon/14.21.1_en_US/`, and loaded into memory. A background task checks for a new version
+//! every `cache.static_data_ttl_secs` and swaps it in when the patch changes. With `cache.static_data_local` set, the files are read from
+//! that directory instead and nothing is downloaded.
+
+use anyhow::{Context as _, Result};
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+pub mod models;
+pub mod search;
+
+use models::{Champion, DataFile, Item, ProfileIcon, Rune, RuneTree, SummonerSpell};
+
+use crate::{config, metrics, shutdown};
+
+const BASE_URL: &str = "https://ddragon.leagueoflegends.com";
+
+pub const CHAMPIONS_FILE: &str = "championFull.json";
+const ITEMS_FILE: &str = "item.json";
+const RUNES_FILE: &str = "runesReforged.json";
+const SPELLS_FILE: &str = "summoner.json";
+const PROFILE_ICONS_FILE: &str = "profileicon.json";
+
+/// The map id of Summoner's Rift in `Item::maps`.
+const SUMMONERS_RIFT: &str = "11";
+
+const FILES: [&str; 5] = [CHAMPIONS_FILE, ITEMS_FILE, RUNES_FILE, SPELLS_FILE, PROFILE_ICONS_FILE];
+
+/// Versions kept on disk; older ones are deleted after a patch change.
+const KEEP_VERSIONS: usize = 2;
+
+lazy_static! {
+    static ref CURRENT: RwLock<Option<Arc<StaticData>>> = RwLock::new(None);
+    /// Held while loading, so concurrent first uses download only once.
+    static ref LOADING: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+    static ref HTTP: reqwest::Client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()
+        .expect("could not build the Data Dragon HTTP client");
+}
+
+/// One Data Dragon version, loaded into memory.
+pub struct StaticData {
+    pub version: String,
+    /// Sorted by name.
+    pub champions: Vec<Champion>,
+    /// Sorted by name, with the Summoner's Rift version of each item first.
+    pub items: Vec<Item>,
+    pub rune_trees: Vec<RuneTree>,
+    /// Sorted by name.
+    pub summoner_spells: Vec<SummonerSpell>,
+    profile_icons: HashSet<i64>,
+    champions_by_key: HashMap<i64, usize>,
+    items_by_id: HashMap<String, usize>,
+}
+
+impl StaticData {
+    /// Loads one version's files from `dir`, e.g. a downloaded version or a directory of fixtures.
+    pub fn load_dir(dir: &Path) -> Result<Self> {
+        fn read<T: serde::de::DeserializeOwned>(dir: &Path, file: &str) -> Result<T> {
+            let path = dir.join(file);
+            let contents = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+            serde_json::from_slice(&contents).with_context(|| format!("parsing {}", path.display()))
+        }
+
+        let champions: DataFile<Champion> = read(dir, CHAMPIONS_FILE)?;
+        let items: DataFile<Item> = read(dir, ITEMS_FILE)?;
+        let rune_trees: Vec<RuneTree> = read(dir, RUNES_FILE)?;
+        let spells: DataFile<SummonerSpell> = read(dir, SPELLS_FILE)?;
+        let icons: DataFile<ProfileIcon> = read(dir, PROFILE_ICONS_FILE)?;
+
+        let mut champions_list: Vec<Champion> = champions.data.into_values().collect();
+        champions_list.sort_by(|a, b| a.name.cmp(&b.name));
+        let champions_by_key = champions_list
+            .iter()
+            .enumerate()
+            .filter_map(|(i, champion)| Some((champion.key.parse().ok()?, i)))
+            .collect();
+
+        let mut items: Vec<Item> = items
+            .data
+            .into_iter()
+            .map(|(id, item)| Item { id, ..item })
+            .collect();
+        // Arena and ARAM copies of an item share its name, so searches should find the Summoner's Rift one
+        let on_rift = |item: &Item| item.maps.get(SUMMONERS_RIFT).copied().unwrap_or(false) && item.gold.purchasable;
+        items.sort_by(|a, b| {
+            a.name
+                .cmp(&b.name)
+                .then_with(|| on_rift(b).cmp(&on_rift(a)))
+                .then_with(|| a.id.len().cmp(&b.id.len()).then_with(|| a.id.cmp(&b.id)))
+        });
+        let items_by_id = items.iter().enumerate().map(|(i, item)| (item.id.clone(), i)).collect();
+
+        let mut summoner_spells: Vec<SummonerSpell> = spells.data.into_values().collect();
+        summoner_spells.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(StaticData {
+            version: champions.version,
+            champions: champions_list,
+            items,
+            rune_trees,
+            summoner_spells,
+            profile_icons: icons.data.into_values().map(|icon| icon.id).collect(),
+            champions_by_key,
+            items_by_id,
+        })
+    }
+
+    /// Looks a champion up by its numeric id, as used by the Riot API.
+    pub fn champion_by_key(&self, key: i64) -> Option<&Champion> {
+        self.champions_by_key.get(&key).map(|&i| &self.champions[i])
+    }
+
+    pub fn item_by_id(&self, id: &str) -> Option<&Item> {
+        self.items_by_id.get(id).map(|&i| &self.items[i])
+    }
+
+    /// Looks a summoner spell up by its numeric id, as in a match's `summoner1Id`.
+    #[allow(dead_code)] // No command shows summoner spells yet
+    pub fn summoner_spell_by_key(&self, key: i64) -> Option<&SummonerSpell> {
+        self.summoner_spells.iter().find(|spell| spell.key.parse() == Ok(key))
+    }
+
+    /// Every rune with its tree and slot (0 is the keystone row).
+    pub fn runes(&self) -> impl Iterator<Item = (&RuneTree, usize, &Rune)> {
+        self.rune_trees.iter().flat_map(|tree| {
+            tree.slots
+                .iter()
+                .enumerate()
+                .flat_map(move |(slot, runes)| runes.runes.iter().map(move |rune| (tree, slot, rune)))
+        })
+    }
+
+    pub fn champion_icon_url(&self, champion: &Champion) -> String {
+        format!("{}/cdn/{}/img/champion/{}", BASE_URL, self.version, champion.image.full)
+    }
+
+    pub fn spell_icon_url(&self, image: &models::Image) -> String {
+        format!("{}/cdn/{}/img/spell/{}", BASE_URL, self.version, image.full)
+    }
+
+    pub fn passive_icon_url(&self, image: &models::Image) -> String {
+        format!("{}/cdn/{}/img/passive/{}", BASE_URL, self.version, image.full)
+    }
+
+    pub fn item_icon_url(&self, item: &Item) -> String {
+        format!("{}/cdn/{}/img/item/{}", BASE_URL, self.version, item.image.full)
+    }
+
+    /// `None` for icons this version does not know, e.g. ones added in a newer patch.
+    #[allow(dead_code)] // No command shows profile icons yet
+    pub fn profile_icon_url(&self, icon_id: i64) -> Option<String> {
+        self.profile_icons
+            .contains(&icon_id)
+            .then(|| format!("{}/cdn/{}/img/profileicon/{}.png", BASE_URL, self.version, icon_id))
+    }
+
+    /// Rune icons are not versioned.
+    pub fn rune_icon_url(&self, icon: &str) -> String {
+        format!("{}/cdn/img/{}", BASE_URL, icon)
+    }
+}
+
+/// The static data if it has been loaded, without waiting for a download.
+pub fn cached() -> Option<Arc<StaticData>> {
+    CURRENT.read().unwrap().clone()
+}
+
+/// The static data, loading it on first use. That first load may download Data Dragon, which can
+/// take longer than Discord's interaction window, so commands defer before calling this.
+pub async fn get() -> Result<Arc<StaticData>> {
+    if let Some(data) = cached() {
+        metrics::record_cache("static_data", true);
+        return Ok(data);
+    }
+    metrics::record_cache("static_data", false);
+
+    let _loading = LOADING.lock().await;
+    if let Some(data) = cached() {
+        return Ok(data);
+    }
+
+    let data = Arc::new(load().await?);
+    tracing::info!("Loaded Data Dragon {}", data.version);
+    *CURRENT.write().unwrap() = Some(data.clone());
+    Ok(data)
+}
+
+/// Loads the repository's `fixtures/ddragon` as the current static data, unless some is loaded.
+#[cfg(test)]
+pub fn use_test_fixtures() {
+    let mut current = CURRENT.write().unwrap();
+    if current.is_none() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/ddragon");
+        *current = Some(Arc::new(StaticData::load_dir(&dir).expect("loading the Data Dragon fixtures")));
+    }
+}
+
+/// The display name of a champion from the Riot API, preferring the loaded Data Dragon names so
+/// champions newer than riven still get one.
+pub fn champion_name(champion: riven::consts::Champion) -> String {
+    cached()
+        .and_then(|data| data.champion_by_key(champion.0 as i64).map(|champion| champion.name.clone()))
+        .or_else(|| champion.name().map(str::to_string))
+        .unwrap_or_else(|| "UNKNOWN".to_string())
+}
+
+/// Turns Data Dragon's markup into plain text: line breaks and list items become new lines and every
+/// other tag is dropped, keeping its text.
+pub fn plain_text(markup: &str) -> String {
+    let mut text = String::with_capacity(markup.len());
+    let mut rest = markup;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim_start_matches('/').to_ascii_lowercase();
+        match tag.split_whitespace().next().unwrap_or_default() {
+            "br" | "br/" => text.push('\n'),
+            "li" if !rest[start + 1..].starts_with('/') => text.push_str("\n• "),
+            _ => {}
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+
+    let text = text.replace("&nbsp;", " ").replace("&amp;", "&").replace("&lt;", "<").replace("&gt;", ">");
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    // Keep paragraph breaks but never more than one blank line in a row
+    let mut result = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if line.is_empty() && (i == 0 || lines[i - 1].is_empty()) {
+            continue;
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+    result.trim().to_string()
+}
+
+/// Checks for a new Data Dragon version right away and then every `cache.static_data_ttl_secs`,
+/// swapping it in when the patch changes. Returns on shutdown.
+pub async fn refresh_periodically() {
+    let cache = &config::get().cache;
+    if cache.static_data_local.is_some() {
+        if let Err(why) = get().await {
+            tracing::error!("Error loading local Data Dragon files: {:#}", why);
+        }
+        return;
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs(cache.static_data_ttl_secs));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown::requested() => return,
+        }
+
+        if let Err(why) = refresh().await {
+            metrics::record_error("ddragon");
+            tracing::warn!("Error refreshing Data Dragon: {:#}", why);
+        }
+    }
+}
+
+async fn refresh() -> Result<()> {
+    let latest = latest_version().await?;
+    if cached().is_some_and(|data| data.version == latest) {
+        return Ok(());
+    }
+
+    let _loading = LOADING.lock().await;
+    let data = Arc::new(load_version(&latest).await?);
+    let previous = CURRENT.write().unwrap().replace(data).map(|data| data.version.clone());
+    match previous {
+        Some(previous) => tracing::info!("Data Dragon updated from {} to {}", previous, latest),
+        None => tracing::info!("Loaded Data Dragon {}", latest),
+    }
+
+    prune_old_versions();
+    Ok(())
+}
+
+/// Loads the local directory if configured, otherwise the newest version, falling back to the newest
+/// version on disk when Data Dragon cannot be reached.
+async fn load() -> Result<StaticData> {
+    let cache = &config::get().cache;
+    if let Some(local) = &cache.static_data_local {
+        let dir = PathBuf::from(local);
+        return tokio::task::spawn_blocking(move || StaticData::load_dir(&dir)).await?;
+    }
+
+    match latest_version().await {
+        Ok(version) => load_version(&version).await,
+        Err(why) => {
+            tracing::warn!("Could not check the Data Dragon version, using the cached files: {:#}", why);
+            let version = versions_on_disk().pop().context("no Data Dragon version is cached on disk")?;
+            let dir = version_dir(&version);
+            tokio::task::spawn_blocking(move || StaticData::load_dir(&dir)).await?
+        }
+    }
+}
+
+async fn latest_version() -> Result<String> {
+    let versions: Vec<String> = HTTP
+        .get(format!("{}/api/versions.json", BASE_URL))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("fetching the Data Dragon versions")?;
+    versions.into_iter().next().context("Data Dragon returned no versions")
+}
+
+/// Downloads whatever files of `version` are not cached yet, then loads it.
+async fn load_version(version: &str) -> Result<StaticData> {
+    let cache = &config::get().cache;
+    let dir = version_dir(version);
+    tokio::fs::create_dir_all(&dir).await.with_context(|| format!("creating {}", dir.display()))?;
+
+    for file in FILES {
+        let path = dir.join(file);
+        if path.exists() {
+            continue;
+        }
+
+        let url = format!("{}/cdn/{}/data/{}/{}", BASE_URL, version, cache.static_data_language, file);
+        tracing::debug!("Downloading {}", url);
+        let body = HTTP
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await
+            .with_context(|| format!("downloading {}", url))?;
+
+        // Write to a temporary file first so an interrupted download is not mistaken for a cached one
+        let partial = path.with_extension("json.tmp");
+        tokio::fs::write(&partial, &body).await.with_context(|| format!("writing {}", partial.display()))?;
+        tokio::fs::rename(&partial, &path).await?;
+    }
+
+    tokio::task::spawn_blocking(move || StaticData::load_dir(&dir)).await?
+}
+
+/// Where one version's files are kept. The language is part of the name since the files are
+/// translated, so changing `cache.static_data_language` downloads them again.
+fn version_dir(version: &str) -> PathBuf {
+    let cache = &config::get().cache;
+    Path::new(&cache.static_data_dir).join(format!("{}_{}", version, cache.static_data_language))
+}
+
+/// Versions cached in the configured language, oldest first.
+fn versions_on_disk() -> Vec<String> {
+    let cache = &config::get().cache;
+    let suffix = format!("_{}", cache.static_data_language);
+    let mut versions: Vec<String> = fs::read_dir(&cache.static_data_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join(CHAMPIONS_FILE).is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok()?.strip_suffix(&suffix).map(str::to_string))
+        .collect();
+
+    let parts = |version: &str| version.split('.').map(|part| part.parse::<u32>().unwrap_or(0)).collect::<Vec<_>>();
+    versions.sort_by_key(|version| parts(version));
+    versions
+}
+
+fn prune_old_versions() {
+    let versions = versions_on_disk();
+    for version in versions.iter().take(versions.len().saturating_sub(KEEP_VERSIONS)) {
+        let path = version_dir(version);
+        match fs::remove_dir_all(&path) {
+            Ok(()) => tracing::debug!("Removed old Data Dragon {}", version),
+            Err(why) => tracing::warn!("Error removing {}: {:?}", path.display(), why),
+        }
+    }
+}
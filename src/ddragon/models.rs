@@ -0,0 +1,135 @@
+//! The parts of the Data Dragon JSON files the bot uses. Everything is optional on Riot's side from
+//! one patch to the next, so missing fields fall back to their defaults.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// `{"version": ..., "data": {<id>: ...}}`, the shape of every file except the runes.
+#[derive(Debug, Deserialize)]
+pub struct DataFile<T> {
+    #[serde(default)]
+    pub version: String,
+    pub data: HashMap<String, T>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Image {
+    pub full: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Champion {
+    /// Internal name used in URLs, e.g. `MonkeyKing`.
+    pub id: String,
+    /// Numeric champion id as a string, e.g. `"62"`.
+    pub key: String,
+    pub name: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub partype: String,
+    pub stats: HashMap<String, f64>,
+    pub spells: Vec<ChampionSpell>,
+    pub passive: Passive,
+    pub image: Image,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ChampionSpell {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub maxrank: u32,
+    pub cooldown_burn: String,
+    pub cost_burn: String,
+    pub range_burn: String,
+    /// How the cost is paid, with `{{ abilityresourcename }}` and `{{ cost }}` placeholders.
+    pub resource: String,
+    pub image: Image,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Passive {
+    pub name: String,
+    pub description: String,
+    pub image: Image,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Gold {
+    pub base: u32,
+    pub total: u32,
+    pub sell: u32,
+    pub purchasable: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Item {
+    /// Filled in from the key of the `data` map.
+    #[serde(skip)]
+    pub id: String,
+    pub name: String,
+    /// HTML-like markup such as `<mainText><stats>...</stats></mainText>`.
+    pub description: String,
+    pub plaintext: String,
+    pub from: Vec<String>,
+    pub into: Vec<String>,
+    pub gold: Gold,
+    pub tags: Vec<String>,
+    pub maps: HashMap<String, bool>,
+    pub stats: HashMap<String, f64>,
+    pub image: Image,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RuneTree {
+    pub id: u32,
+    pub key: String,
+    pub icon: String,
+    pub name: String,
+    pub slots: Vec<RuneSlot>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RuneSlot {
+    pub runes: Vec<Rune>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Rune {
+    pub id: u32,
+    pub key: String,
+    pub icon: String,
+    pub name: String,
+    pub short_desc: String,
+    pub long_desc: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SummonerSpell {
+    pub id: String,
+    /// Numeric spell id as a string, e.g. `"4"` for Flash.
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    pub cooldown_burn: String,
+    pub summoner_level: u32,
+    /// Game modes the spell is available in, e.g. `CLASSIC`.
+    pub modes: Vec<String>,
+    pub image: Image,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProfileIcon {
+    pub id: i64,
+}
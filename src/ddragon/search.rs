@@ -0,0 +1,334 @@
+//! Finding champions, items and runes by the names users type.
+
+use std::fmt;
+
+use super::models::{Champion, Item, Rune, RuneTree};
+use super::StaticData;
+use crate::utils;
+
+/// Short names players use that are not a prefix of the champion's name, by Data Dragon id.
+const CHAMPION_ALIASES: &[(&str, &[&str])] = &[
+    ("AurelionSol", &["asol"]),
+    ("Blitzcrank", &["blitz"]),
+    ("Cassiopeia", &["cass", "cassio"]),
+    ("Chogath", &["cho"]),
+    ("DrMundo", &["mundo"]),
+    ("Fiddlesticks", &["fiddle"]),
+    ("Gangplank", &["gp"]),
+    ("Heimerdinger", &["heimer", "donger"]),
+    ("JarvanIV", &["j4", "jarvan"]),
+    ("Kassadin", &["kass"]),
+    ("KogMaw", &["kog"]),
+    ("Leblanc", &["lb"]),
+    ("LeeSin", &["lee"]),
+    ("MasterYi", &["yi"]),
+    ("MissFortune", &["mf"]),
+    ("MonkeyKing", &["wu", "monkey king"]),
+    ("Nautilus", &["naut"]),
+    ("Nidalee", &["nid"]),
+    ("Nunu", &["nunu and willump"]),
+    ("Seraphine", &["sera"]),
+    ("TahmKench", &["tahm", "tk"]),
+    ("TwistedFate", &["tf"]),
+    ("Tryndamere", &["trynd", "trynda"]),
+    ("Warwick", &["ww"]),
+    ("XinZhao", &["xin"]),
+];
+
+/// Short names players use for items, by item name.
+const ITEM_ALIASES: &[(&str, &[&str])] = &[
+    ("Blade of The Ruined King", &["bork", "botrk"]),
+    ("Guardian Angel", &["ga"]),
+    ("Infinity Edge", &["ie"]),
+    ("Lord Dominik's Regards", &["ldr"]),
+    ("Mercury's Treads", &["mercs"]),
+    ("Plated Steelcaps", &["tabi", "steelcaps"]),
+    ("Quicksilver Sash", &["qss"]),
+    ("Rabadon's Deathcap", &["dcap", "deathcap"]),
+    ("Rapid Firecannon", &["rfc"]),
+    ("Sorcerer's Shoes", &["sorcs"]),
+    ("The Collector", &["collector"]),
+    ("Trinity Force", &["tri", "triforce"]),
+    ("Zhonya's Hourglass", &["zhonyas", "zhonya"]),
+];
+
+/// A name that matches nothing, with the closest names for the error message.
+#[derive(Debug, Clone)]
+pub struct NotFound {
+    /// What was looked for, e.g. `champion`.
+    pub kind: &'static str,
+    pub input: String,
+    pub suggestions: Vec<String>,
+}
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown {} `{}`.", self.kind, self.input)?;
+        if !self.suggestions.is_empty() {
+            let suggestions: Vec<String> = self.suggestions.iter().map(|name| format!("`{}`", name)).collect();
+            write!(f, " Did you mean {}?", suggestions.join(" or "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// Finds the entry `input` names, trying in order: an exact name (ignoring case, spaces and
+/// punctuation), the only name starting with the input, the only name containing it, and the name
+/// clearly closest to it in spelling. Otherwise the error suggests the entries starting with or
+/// containing the input, or else the closest ones.
+/// `names` returns an entry's display name first, then any other names it answers to. Entries
+/// sharing a display name count as one, and the first of them is returned.
+pub fn find<'a, T>(
+    kind: &'static str,
+    input: &str,
+    entries: &'a [T],
+    names: impl Fn(&'a T) -> Vec<&'a str>,
+) -> Result<&'a T, NotFound> {
+    let normalized = utils::normalize(input);
+    let not_found = |found: Vec<&'a T>| NotFound {
+        kind,
+        input: input.trim().to_string(),
+        suggestions: found.into_iter().take(3).map(|entry| names(entry)[0].to_string()).collect(),
+    };
+    if normalized.is_empty() {
+        return Err(not_found(Vec::new()));
+    }
+
+    if let Some(entry) = entries.iter().find(|entry| names(entry).iter().any(|name| utils::normalize(name) == normalized)) {
+        return Ok(entry);
+    }
+
+    let prefixed = distinct(
+        entries.iter().filter(|entry| names(entry).iter().any(|name| utils::normalize(name).starts_with(&normalized))),
+        &names,
+    );
+    match prefixed[..] {
+        [entry] => return Ok(entry),
+        // A short input like `ka` names several entries; list them instead of guessing by spelling
+        [_, _, ..] => return Err(not_found(prefixed)),
+        [] => {}
+    }
+
+    // Words in the middle of a name, like `aery` for Summon Aery, but not single letters
+    if normalized.chars().count() >= 3 {
+        let containing = distinct(
+            entries.iter().filter(|entry| names(entry).iter().any(|name| utils::normalize(name).contains(&normalized))),
+            &names,
+        );
+        match containing[..] {
+            [entry] => return Ok(entry),
+            [_, _, ..] => return Err(not_found(containing)),
+            [] => {}
+        }
+    }
+
+    let close = closest(input, entries, &names);
+    match close[..] {
+        [(_, entry)] => Ok(entry),
+        [(best, entry), (second, _), ..] if best < second => Ok(entry),
+        _ => Err(not_found(close.into_iter().map(|(_, entry)| entry).collect())),
+    }
+}
+
+/// Display names for autocompleting `input`: names starting with it, then names containing it, then
+/// names within typing distance. An empty input lists the first entries.
+pub fn suggest<'a, T>(input: &str, entries: &'a [T], names: impl Fn(&'a T) -> Vec<&'a str>, limit: usize) -> Vec<String> {
+    let normalized = utils::normalize(input);
+
+    let mut found = Vec::new();
+    let mut containing = Vec::new();
+    for entry in entries {
+        let entry_names: Vec<String> = names(entry).iter().map(|name| utils::normalize(name)).collect();
+        if entry_names.iter().any(|name| name.starts_with(&normalized)) {
+            found.push(entry);
+        } else if entry_names.iter().any(|name| name.contains(&normalized)) {
+            containing.push(entry);
+        }
+    }
+    found.extend(containing);
+    if found.len() < limit {
+        found.extend(closest(input, entries, &names).into_iter().map(|(_, entry)| entry));
+    }
+
+    distinct(found, &names).into_iter().take(limit).map(|entry| names(entry)[0].to_string()).collect()
+}
+
+/// The entries with a name within typing distance of `input`, closest first, with their distance.
+/// A name's beginning counts too, one edit further away, so `morelo` still finds Morellonomicon.
+fn closest<'a, T>(input: &str, entries: &'a [T], names: &impl Fn(&'a T) -> Vec<&'a str>) -> Vec<(usize, &'a T)> {
+    let input = utils::normalize(input);
+    let length = input.chars().count();
+    let max_distance = (length / 3).max(2);
+
+    let mut scored: Vec<(usize, &T)> = entries
+        .iter()
+        .filter_map(|entry| {
+            let distance = names(entry)
+                .iter()
+                .map(|name| {
+                    let name = utils::normalize(name);
+                    let beginning: String = name.chars().take(length).collect();
+                    utils::edit_distance(&input, &name).min(utils::edit_distance(&input, &beginning) + 1)
+                })
+                .min()?;
+            (distance <= max_distance).then_some((distance, entry))
+        })
+        .collect();
+    scored.sort_by_key(|(distance, _)| *distance);
+
+    let mut seen = Vec::new();
+    scored.retain(|(_, entry)| {
+        let name = names(entry)[0];
+        if seen.contains(&name) {
+            return false;
+        }
+        seen.push(name);
+        true
+    });
+    scored
+}
+
+/// `entries` without the ones whose display name was already seen.
+fn distinct<'a, T>(entries: impl IntoIterator<Item = &'a T>, names: &impl Fn(&'a T) -> Vec<&'a str>) -> Vec<&'a T> {
+    let mut seen = Vec::new();
+    let mut distinct = Vec::new();
+    for entry in entries {
+        let name = names(entry)[0];
+        if !seen.contains(&name) {
+            seen.push(name);
+            distinct.push(entry);
+        }
+    }
+    distinct
+}
+
+impl StaticData {
+    /// Finds a champion by name, Data Dragon id (`MonkeyKing`) or common short name (`tf`, `j4`).
+    pub fn find_champion(&self, input: &str) -> Result<&Champion, NotFound> {
+        find("champion", input, &self.champions, champion_names)
+    }
+
+    pub fn suggest_champions(&self, input: &str, limit: usize) -> Vec<String> {
+        suggest(input, &self.champions, champion_names, limit)
+    }
+
+    /// Finds an item by name or common short name (`dcap`, `bork`), preferring the Summoner's Rift
+    /// version of items that exist on several maps.
+    pub fn find_item(&self, input: &str) -> Result<&Item, NotFound> {
+        find("item", input, &self.items, item_names)
+    }
+
+    pub fn suggest_items(&self, input: &str, limit: usize) -> Vec<String> {
+        suggest(input, &self.items, item_names, limit)
+    }
+
+    /// Finds a rune by name, with its tree and slot.
+    pub fn find_rune(&self, input: &str) -> Result<(&RuneTree, usize, &Rune), NotFound> {
+        let runes: Vec<_> = self.runes().collect();
+        let found = find("rune", input, &runes, |(_, _, rune)| vec![rune.name.as_str(), rune.key.as_str()])?;
+        Ok(*found)
+    }
+
+    pub fn suggest_runes(&self, input: &str, limit: usize) -> Vec<String> {
+        let runes: Vec<_> = self.runes().collect();
+        suggest(input, &runes, |(_, _, rune)| vec![rune.name.as_str(), rune.key.as_str()], limit)
+    }
+}
+
+fn champion_names(champion: &Champion) -> Vec<&str> {
+    let aliases = CHAMPION_ALIASES
+        .iter()
+        .find(|(id, _)| *id == champion.id)
+        .map(|(_, aliases)| *aliases)
+        .unwrap_or_default();
+    [champion.name.as_str(), champion.id.as_str()].into_iter().chain(aliases.iter().copied()).collect()
+}
+
+fn item_names(item: &Item) -> Vec<&str> {
+    let aliases = ITEM_ALIASES
+        .iter()
+        .find(|(name, _)| *name == item.name)
+        .map(|(_, aliases)| *aliases)
+        .unwrap_or_default();
+    std::iter::once(item.name.as_str()).chain(aliases.iter().copied()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn fixtures() -> StaticData {
+        StaticData::load_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/ddragon")).unwrap()
+    }
+
+    #[test]
+    fn finds_champions_by_name_id_and_alias() {
+        let data = fixtures();
+        assert_eq!(data.find_champion("twisted fate").unwrap().name, "Twisted Fate");
+        assert_eq!(data.find_champion("tf").unwrap().name, "Twisted Fate");
+        assert_eq!(data.find_champion("LeeSin").unwrap().name, "Lee Sin");
+        assert_eq!(data.find_champion("ori").unwrap().name, "Orianna");
+        assert_eq!(data.find_champion("viktr").unwrap().name, "Viktor");
+        assert_eq!(data.champion_by_key(7).unwrap().name, "LeBlanc");
+    }
+
+    #[test]
+    fn lists_candidates_for_ambiguous_or_unknown_champions() {
+        let data = fixtures();
+        let ambiguous = data.find_champion("l").unwrap_err();
+        assert_eq!(ambiguous.suggestions, ["LeBlanc", "Lee Sin"]);
+
+        let unknown = data.find_champion("nasus").unwrap_err();
+        assert!(unknown.suggestions.is_empty());
+        assert_eq!(unknown.to_string(), "Unknown champion `nasus`.");
+    }
+
+    #[test]
+    fn finds_items_by_name_alias_and_spelling() {
+        let data = fixtures();
+        assert_eq!(data.find_item("dcap").unwrap().name, "Rabadon's Deathcap");
+        assert_eq!(data.find_item("zhonyas").unwrap().name, "Zhonya's Hourglass");
+        assert_eq!(data.find_item("morelo").unwrap().name, "Morellonomicon");
+        assert_eq!(data.find_item("boots").unwrap().name, "Boots");
+        assert_eq!(data.item_by_id("3089").unwrap().name, "Rabadon's Deathcap");
+    }
+
+    #[test]
+    fn finds_runes_with_their_tree_and_slot() {
+        let data = fixtures();
+        let (tree, slot, rune) = data.find_rune("aery").unwrap();
+        assert_eq!((tree.name.as_str(), slot, rune.name.as_str()), ("Sorcery", 0, "Summon Aery"));
+
+        let (_, slot, rune) = data.find_rune("taste of blod").unwrap();
+        assert_eq!((slot, rune.name.as_str()), (1, "Taste of Blood"));
+    }
+
+    #[test]
+    fn loads_summoner_spells_and_profile_icons() {
+        let data = fixtures();
+        let names: Vec<&str> = data.summoner_spells.iter().map(|spell| spell.name.as_str()).collect();
+        assert_eq!(names, ["Flash", "Ignite", "Teleport"]);
+        assert_eq!(data.summoner_spell_by_key(4).unwrap().name, "Flash");
+        assert_eq!(data.summoner_spell_by_key(14).unwrap().cooldown_burn, "180");
+        assert!(data.summoner_spell_by_key(99).is_none());
+
+        assert_eq!(
+            data.profile_icon_url(588).as_deref(),
+            Some("https://ddragon.leagueoflegends.com/cdn/14.21.1/img/profileicon/588.png")
+        );
+        assert_eq!(data.profile_icon_url(7), None);
+    }
+
+    #[test]
+    fn suggests_prefixes_before_other_matches() {
+        let data = fixtures();
+        assert_eq!(data.suggest_champions("le", 25), ["LeBlanc", "Lee Sin", "Twisted Fate"]);
+        assert_eq!(data.suggest_champions("", 2), ["Ahri", "LeBlanc"]);
+        assert_eq!(data.suggest_items("rod", 25), ["Needlessly Large Rod"]);
+        assert_eq!(data.suggest_runes("a", 3), ["Arcane Comet", "Dark Harvest", "Cheap Shot"]);
+    }
+}
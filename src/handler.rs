@@ -1,94 +1,339 @@
 use serenity::async_trait;
 use serenity::model::channel::Message;
 use serenity::prelude::*;
-use std::collections::HashMap;
-use serenity::all::{ EditMessage, Interaction};
+use std::collections::BTreeMap;
+use std::time::Instant;
+use serenity::all::{
+    Command as SlashCommand, CommandInteraction, ComponentInteraction, ComponentInteractionDataKind,
+    CreateAutocompleteResponse, CreateCommand, CreateInteractionResponse, Interaction, Ready, ShardStageUpdateEvent,
+    UserId,
+};
+use tracing::Instrument;
 
+use crate::commands::invocation::{ComponentSink, InteractionSink, MessageSink};
+use crate::commands::{self, Command, ComponentId, ComponentPress, Invocation, Reply};
+use crate::config;
+use crate::cooldown::{CooldownLimits, Cooldowns};
+use crate::metrics;
+use crate::permissions::{self, PermissionLevel};
+use crate::settings::SETTINGS;
+use crate::shutdown;
 
+pub const DEFAULT_PREFIX: &str = "/kir ";
 
-use crate::commands::mastery::MasteryCommand;
-use crate::commands::about::AboutCommand;
-use crate::commands::help::HelpCommand;
-use crate::commands::profile::ProfileCommand;
-use crate::commands::region::RegionCommand;
-use crate::commands::Command;
+/// Discord shows at most 25 autocomplete choices.
+const AUTOCOMPLETE_CHOICES: usize = 25;
 
 pub struct CommandHandler {
-    commands: HashMap<String, Box<dyn Command>>,
+    commands: BTreeMap<String, Box<dyn Command>>,
+    cooldowns: Cooldowns,
 }
 
 impl CommandHandler {
     pub fn new() -> Self {
-        let mut commands: HashMap<String, Box<dyn Command>> = HashMap::new();
-
-        commands.insert("mastery".to_string(), Box::new(MasteryCommand::new()));
-        commands.insert("about".to_string(), Box::new(AboutCommand::new()));
-        commands.insert("profile".to_string(), Box::new(ProfileCommand::new()));
-        commands.insert("help".to_string(), Box::new(HelpCommand::new()));
-        commands.insert("region".to_string(), Box::new(RegionCommand::new()));
+        let cooldowns = Cooldowns::new(CooldownLimits::from_config(&config::get().cooldowns));
+        CommandHandler { commands: commands::all(), cooldowns }
+    }
 
-        CommandHandler { commands }
+    /// The slash commands to register, sorted by name.
+    pub fn slash_commands(&self) -> Vec<CreateCommand> {
+        self.commands.values().map(|command| commands::slash_command(command.as_ref())).collect()
     }
 
     pub async fn handle_command(&self, ctx: &Context, msg: &Message, command_input: &str) {
         let parts: Vec<&str> = command_input.split_whitespace().collect();
-        if let Some(command_name) = parts.get(0).map(|s| *s) {
-            if let Some(command) = self.commands.get(command_name) {
-                let _ = command.handle(ctx, msg, &command_input[command_name.len()..].trim()).await;
+        if let Some(command_name) = parts.first().copied() {
+            // Prefixes match in any case, so command names do too
+            if let Some(command) = self.commands.get(command_name.to_lowercase().as_str()) {
+                let span = tracing::info_span!(
+                    "command",
+                    command = command_name,
+                    user = msg.author.id.get(),
+                    guild = msg.guild_id.map(|id| id.get()),
+                    channel = msg.channel_id.get(),
+                );
+                let input = command_input[command_name.len()..].trim();
+                let sink = MessageSink::new(ctx.http.clone(), msg.channel_id);
+                async {
+                    let level = permissions::message_level(ctx, msg).await;
+                    let invocation = Invocation::new(msg.guild_id, Some(msg.author.id), input, &sink).level(level);
+                    self.run_command(command.as_ref(), &invocation, msg.author.id).await;
+                }
+                .instrument(span)
+                .await;
             } else {
+                tracing::debug!(command = command_name, "Unknown command");
                 let _ = msg.channel_id.say(&ctx.http, "Unknown command.").await;
             }
         }
     }
+
+    /// Runs a slash command. Its text arguments arrive in the `input` option, and an optional `region`
+    /// option is passed on as a `region:` override.
+    pub async fn handle_slash(&self, ctx: &Context, interaction: &CommandInteraction) {
+        let Some(command) = self.commands.get(interaction.data.name.as_str()) else {
+            tracing::debug!(command = %interaction.data.name, "Unknown slash command");
+            return;
+        };
+
+        let span = tracing::info_span!(
+            "command",
+            command = %interaction.data.name,
+            user = interaction.user.id.get(),
+            guild = interaction.guild_id.map(|id| id.get()),
+            channel = interaction.channel_id.get(),
+        );
+        let option = |name: &str| {
+            interaction.data.options.iter().find(|option| option.name == name).and_then(|option| option.value.as_str())
+        };
+        let mut input = option("input").unwrap_or_default().to_string();
+        if let Some(region) = option("region") {
+            input.push_str(&format!(" region:{}", region.replace(' ', "")));
+        }
+        let sink = InteractionSink::new(ctx.http.clone(), interaction.clone());
+        let level = permissions::interaction_level(interaction.user.id, interaction.guild_id, interaction.member.as_deref());
+        let invocation = Invocation::new(interaction.guild_id, Some(interaction.user.id), &input, &sink).level(level);
+        self.run_command(command.as_ref(), &invocation, interaction.user.id).instrument(span).await;
+    }
+
+    /// Answers an autocomplete request for a slash command's `input` option.
+    pub async fn handle_autocomplete(&self, ctx: &Context, interaction: &CommandInteraction) {
+        let Some(command) = self.commands.get(interaction.data.name.as_str()) else {
+            return;
+        };
+        let Some(focused) = interaction.data.autocomplete() else {
+            return;
+        };
+
+        let choices = if focused.name == "input" { command.autocomplete(focused.value) } else { Vec::new() };
+        let response = choices
+            .into_iter()
+            .take(AUTOCOMPLETE_CHOICES)
+            .fold(CreateAutocompleteResponse::new(), |response, choice| response.add_string_choice(choice.clone(), choice));
+        if let Err(why) = interaction.create_response(&ctx.http, CreateInteractionResponse::Autocomplete(response)).await {
+            metrics::record_error("discord");
+            tracing::debug!(command = %interaction.data.name, "Error answering autocomplete: {:?}", why);
+        }
+    }
+
+    /// Routes a button or menu press to the command named in its [`ComponentId`]. Presses on expired
+    /// components, or on components from before the ids were encoded, get a polite ephemeral reply.
+    pub async fn handle_component(&self, ctx: &Context, interaction: &ComponentInteraction) {
+        let span = tracing::info_span!(
+            "component",
+            custom_id = %interaction.data.custom_id,
+            user = interaction.user.id.get(),
+            guild = interaction.guild_id.map(|id| id.get()),
+        );
+
+        async {
+            let sink = ComponentSink::new(ctx.http.clone(), interaction.clone());
+            let level =
+                permissions::interaction_level(interaction.user.id, interaction.guild_id, interaction.member.as_ref());
+            let invocation = Invocation::new(interaction.guild_id, Some(interaction.user.id), "", &sink).level(level);
+
+            let id = ComponentId::parse(&interaction.data.custom_id);
+            let Some((id, command)) = id.and_then(|id| Some((id.clone(), self.commands.get(&id.command)?))) else {
+                tracing::debug!("Component is not owned by any command");
+                invocation.send(Reply::text("This menu is no longer active.").ephemeral(true)).await;
+                return;
+            };
+
+            if id.is_expired() {
+                tracing::debug!("Component has expired");
+                let response = format!("This menu has expired. Run `{}` again to get a new one.", id.command);
+                invocation.send(Reply::text(response).ephemeral(true)).await;
+                return;
+            }
+
+            if !SETTINGS.get(interaction.guild_id).is_command_enabled(command.name()) {
+                invocation.send(Reply::text("This command is disabled on this server.").ephemeral(true)).await;
+                return;
+            }
+
+            if invocation.level < command.permission() {
+                tracing::info!(required = ?command.permission(), "Permission denied");
+                invocation.send(Reply::text(command.permission().denied_message()).ephemeral(true)).await;
+                return;
+            }
+
+            let values = match &interaction.data.kind {
+                ComponentInteractionDataKind::StringSelect { values } => values.clone(),
+                _ => Vec::new(),
+            };
+            command.handle_component(&invocation, &ComponentPress { id, values }).await;
+        }
+        .instrument(span)
+        .await;
+    }
+
+    /// Checks that the command may run at the invocation's permission level, then runs it.
+    async fn run_command(&self, command: &dyn Command, invocation: &Invocation<'_>, user_id: UserId) {
+        let level = invocation.level;
+        let started = Instant::now();
+        let settings = SETTINGS.get(invocation.guild_id);
+
+        if !settings.is_command_enabled(command.name()) {
+            tracing::info!("Command is disabled on this server");
+            invocation.send(Reply::text("This command is disabled on this server.").ephemeral(true)).await;
+            return;
+        }
+
+        if level < command.permission() {
+            tracing::info!(required = ?command.permission(), "Permission denied");
+            invocation.send(Reply::text(command.permission().denied_message()).ephemeral(true)).await;
+            return;
+        }
+
+        let guild_id = invocation.guild_id.map(|id| id.get());
+        if let Err(wait) = self.cooldowns.try_acquire(command.name(), command.cooldown(), user_id.get(), guild_id) {
+            // Bot admins and owners are exempt from cooldowns
+            if level < PermissionLevel::Admin {
+                tracing::info!(wait_secs = wait.as_secs(), "Command on cooldown");
+                let response = format!("⏳ Slow down! Try again in {}s.", wait.as_secs().max(1));
+                invocation.send(Reply::text(response).ephemeral(true)).await;
+                return;
+            }
+        }
+
+        metrics::record_command(command.name());
+        command.run(invocation).await;
+        tracing::info!(elapsed_ms = started.elapsed().as_millis() as u64, "Command handled");
+    }
 }
 
 pub struct Handler {
     command_handler: CommandHandler,
-    region_command: RegionCommand,
 }
 
 impl Handler {
     pub fn new() -> Self {
-        let command_handler = CommandHandler::new();
-        let region_command = RegionCommand::new(); 
-        Handler { command_handler, region_command }
+        Handler { command_handler: CommandHandler::new() }
     }   
 }
+/// Strips `prefix` from the start of `content`, ignoring case. Prefixes ending in a letter or digit
+/// must be followed by whitespace, so `kir` matches `kir profile` but not `kirby`.
+fn strip_prefix<'a>(content: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = content.get(..prefix.len())?;
+    if !head.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+
+    let rest = &content[prefix.len()..];
+    let needs_space = prefix.chars().last().is_some_and(char::is_alphanumeric);
+    if needs_space && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    Some(rest)
+}
+
+/// Strips a leading `<@id>` or `<@!id>` mention of the bot from `content`.
+fn strip_mention(content: &str, bot_id: u64) -> Option<&str> {
+    [format!("<@{}>", bot_id), format!("<@!{}>", bot_id)]
+        .iter()
+        .find_map(|mention| content.strip_prefix(mention.as_str()))
+}
+
 #[async_trait]
 impl EventHandler for Handler {
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        match ready.shard {
+            Some(shard) => tracing::info!(
+                shard = shard.id.0,
+                "Shard {}/{} ready with {} guilds",
+                shard.id.0,
+                shard.total,
+                ready.guilds.len()
+            ),
+            None => tracing::info!("Ready with {} guilds", ready.guilds.len()),
+        }
 
-    async fn message(&self, ctx: Context, msg: Message) {
-        if msg.content.starts_with("/kir ") {
-            let command_input = &msg.content[5..].trim().to_string();
-            self.command_handler.handle_command(&ctx, &msg, command_input).await;
+        // Slash commands are global, so the first shard registers them for everyone
+        if ready.shard.is_none_or(|shard| shard.id.0 == 0) {
+            match SlashCommand::set_global_commands(&ctx.http, self.command_handler.slash_commands()).await {
+                Ok(registered) => tracing::info!("Registered {} slash commands", registered.len()),
+                Err(why) => {
+                    metrics::record_error("discord");
+                    tracing::error!("Error registering slash commands: {:?}", why);
+                }
+            }
         }
     }
 
-    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Some(data) = interaction.clone().message_component() {
-            match data.data.custom_id.as_str() {
-                "region_select" => {
-                    
-                    let region_command = &self.region_command; 
-                    
-                    region_command.handle_interaction(&ctx, &interaction).await;
-    
-                    let current_region_str = RegionCommand::get_region_string();
-                    let response_message = format!("📍 **Current Region:** `{}`", current_region_str);
-    
-                    let update_builder = EditMessage::new().content(response_message);
-                    if let Err(e) = ctx.http.edit_original_interaction_response(&interaction.token(), &update_builder, vec![]).await {
-                        println!("Error updating interaction response: {:?}", e);
-                    }
+    async fn shard_stage_update(&self, _ctx: Context, event: ShardStageUpdateEvent) {
+        tracing::info!(shard = event.shard_id.0, "Shard is {}", event.new);
+        metrics::set_shard_stage(event.shard_id.0, event.new.to_string());
+    }
+
+    async fn message(&self, ctx: Context, msg: Message) {
+        if msg.author.bot || shutdown::is_requested() {
+            return;
+        }
+
+        let settings = SETTINGS.get(msg.guild_id);
+        let bot_id = ctx.cache.current_user().id;
+
+        let command_input = strip_mention(&msg.content, bot_id.get())
+            .or_else(|| settings.prefixes().into_iter().find_map(|prefix| strip_prefix(&msg.content, prefix)))
+            .or_else(|| msg.guild_id.is_none().then_some(msg.content.as_str()));
+
+        if let Some(command_input) = command_input {
+            // Admins can always reach `config`, so a channel restriction can be undone
+            if !settings.is_channel_allowed(msg.channel_id.get()) {
+                let name = command_input.split_whitespace().next().unwrap_or_default();
+                let is_config = name.eq_ignore_ascii_case("config");
+                if !is_config || permissions::message_level(&ctx, &msg).await < PermissionLevel::Admin {
+                    return;
                 }
-                _ => println!("Unhandled interaction type: {:?}", data.data.custom_id),
             }
-        } else {
-            println!("No message component found for interaction.");
+
+            let _in_flight = shutdown::track();
+            let command_input = command_input.trim().to_string();
+            self.command_handler.handle_command(&ctx, &msg, &command_input).await;
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if shutdown::is_requested() {
+            return;
         }
+        let _in_flight = shutdown::track();
+
+        match &interaction {
+            Interaction::Command(command) => self.command_handler.handle_slash(&ctx, command).await,
+            Interaction::Component(component) => self.command_handler.handle_component(&ctx, component).await,
+            Interaction::Autocomplete(autocomplete) => self.command_handler.handle_autocomplete(&ctx, autocomplete).await,
+            _ => tracing::debug!("Ignoring interaction of kind {:?}", interaction.kind()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_prefixes_in_any_case() {
+        assert_eq!(strip_prefix("/kir profile Faker#KR1", "/kir "), Some("profile Faker#KR1"));
+        assert_eq!(strip_prefix("/KIR Profile", "/kir "), Some("Profile"));
+        assert_eq!(strip_prefix("!profile", "!"), Some("profile"));
+        assert_eq!(strip_prefix("/ki", "/kir "), None);
+        assert_eq!(strip_prefix("?profile", "!"), None);
     }
-    
-    
 
+    #[test]
+    fn word_prefixes_need_a_space_after_them() {
+        assert_eq!(strip_prefix("kir profile", "kir"), Some(" profile"));
+        assert_eq!(strip_prefix("kirby", "kir"), None);
+        assert_eq!(strip_prefix("kir", "kir"), None);
+    }
 
+    #[test]
+    fn strips_mentions_of_the_bot_only() {
+        assert_eq!(strip_mention("<@42> profile", 42), Some(" profile"));
+        assert_eq!(strip_mention("<@!42>help", 42), Some("help"));
+        assert_eq!(strip_mention("<@43> profile", 42), None);
+        assert_eq!(strip_mention("profile <@42>", 42), None);
+    }
 }
@@ -1,43 +1,262 @@
 use serenity::Client;
 use serenity::model::gateway::GatewayIntents;
+use serenity::all::GuildId;
 use std::env;
 use std::error::Error;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
 use dotenvy::dotenv;
-//use tracing::{info, warn}; 
-use tracing_subscriber; 
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::EnvFilter;
 
+mod cli;
 mod commands;
+mod config;
+mod cooldown;
+mod ddragon;
 mod handler;
+mod metrics;
+mod permissions;
+mod riot;
+mod settings;
+mod sharding;
+mod shutdown;
+mod trackers;
+mod utils;
 
+use config::{Config, LoggingConfig, ShardingMode};
 use handler::Handler;
+use settings::SETTINGS;
+
+/// How long running commands get to finish after SIGTERM or Ctrl+C.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+const USAGE: &str = "Usage: lol-discord-bot [--config <path>] [--fixtures <dir>] [--check-config]\n       \
+                     lol-discord-bot --cli [--config <path>] [--fixtures <dir>] [--guild <id>] [<command>...]";
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    // Load the .env file
-    dotenv()?;
-    tracing_subscriber::fmt::init();
+async fn main() -> ExitCode {
+    // A .env file is optional; values can also come from the config file or the real environment
+    let _ = dotenv();
+
+    let mut config_path = PathBuf::from(config::DEFAULT_CONFIG_PATH);
+    let mut check_only = false;
+    let mut cli_mode = false;
+    let mut guild_id = None;
+    let mut fixtures = None;
+    let mut cli_input = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check-config" => check_only = true,
+            "--cli" => cli_mode = true,
+            "--config" | "--fixtures" | "--guild" => {
+                let Some(value) = args.next() else {
+                    eprintln!("{} expects a value\n{}", arg, USAGE);
+                    return ExitCode::FAILURE;
+                };
+                match arg.as_str() {
+                    "--config" => config_path = PathBuf::from(value),
+                    "--fixtures" => fixtures = Some(value),
+                    _ => match value.parse() {
+                        Ok(id) => guild_id = Some(GuildId::new(id)),
+                        Err(_) => {
+                            eprintln!("--guild expects a guild id, got `{}`", value);
+                            return ExitCode::FAILURE;
+                        }
+                    },
+                }
+            }
+            // In CLI mode everything after the options is the command to run
+            _ if cli_mode && !arg.starts_with("--") => {
+                cli_input.push(arg);
+                cli_input.extend(args.by_ref());
+            }
+            _ => {
+                eprintln!("Unknown argument `{}`\n{}", arg, USAGE);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let config = Config::load(&config_path).and_then(|mut config| {
+        if fixtures.is_some() {
+            config.riot.fixtures = fixtures;
+        }
+        // Offline runs also take their Data Dragon files from the fixtures when they have some
+        if let (Some(dir), None) = (&config.riot.fixtures, &config.cache.static_data_local) {
+            let local = PathBuf::from(dir).join("ddragon");
+            if local.is_dir() {
+                config.cache.static_data_local = Some(local.to_string_lossy().into_owned());
+            }
+        }
+        if cli_mode {
+            config.validate_offline()?;
+        } else {
+            config.validate()?;
+        }
+        Ok(config)
+    });
+    let config = match config {
+        Ok(config) => config,
+        Err(why) => {
+            eprintln!("{}: {:#}", config_path.display(), why);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if check_only {
+        println!("{}: configuration is valid", config_path.display());
+        return ExitCode::SUCCESS;
+    }
+
+    init_logging(&config.logging, cli_mode);
+
+    let fixtures = config.riot.fixtures.clone();
+    config::init(config);
+    // Read the settings now, so a store that cannot be read stops the bot before it starts
+    lazy_static::initialize(&SETTINGS);
+
+    if let Some(dir) = fixtures {
+        if let Err(why) = riot::use_fixtures(PathBuf::from(dir)).await {
+            eprintln!("Could not serve Riot fixtures: {:#}", why);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if cli_mode {
+        if let Err(why) = ddragon::get().await {
+            tracing::warn!("Could not load Data Dragon: {:#}", why);
+        }
+        let input = (!cli_input.is_empty()).then(|| cli_input.join(" "));
+        return match cli::run(guild_id, input).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(why) => {
+                eprintln!("{:#}", why);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(why) => {
+            eprintln!("{}", why);
+            ExitCode::FAILURE
+        }
+    }
+}
 
-    let token = env::var("DISCORD_TOKEN")
-        .map_err(|_| "Expected a token in the environment")?;
+/// Starts the shards this process is configured to run.
+async fn start(client: &mut Client) -> serenity::Result<()> {
+    let sharding = &config::get().sharding;
+    match sharding.mode {
+        ShardingMode::Single => client.start().await,
+        ShardingMode::Auto => client.start_autosharded().await,
+        ShardingMode::Range => {
+            tracing::info!("Starting shards {}-{} of {}", sharding.first, sharding.last, sharding.total);
+            // Serenity treats the end of the range as inclusive
+            client.start_shard_range(sharding.first..sharding.last, sharding.total).await
+        }
+    }
+}
+
+/// In CLI mode logs go to stderr, so stdout only carries the command replies.
+fn init_logging(logging: &LoggingConfig, cli_mode: bool) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&logging.level));
+    let writer = if cli_mode { BoxMakeWriter::new(std::io::stderr) } else { BoxMakeWriter::new(std::io::stdout) };
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter).with_writer(writer);
+
+    if logging.format == "json" {
+        // Include the fields of the enclosing command span on every event
+        subscriber.json().with_current_span(true).with_span_list(false).init();
+    } else {
+        subscriber.init();
+    }
+}
+
+async fn run() -> Result<(), Box<dyn Error>> {
+    let token = &config::get().discord.token;
 
     // Define intents for the bot
-    let intents = GatewayIntents::GUILD_MESSAGES
+    let intents = GatewayIntents::GUILDS
+                | GatewayIntents::GUILD_MESSAGES
                 | GatewayIntents::DIRECT_MESSAGES
                 | GatewayIntents::MESSAGE_CONTENT;
 
 
     let handler = Handler::new();
 
-    let mut client = Client::builder(&token, intents)
+    let mut client = Client::builder(token, intents)
         .event_handler(handler)
         .await
         .map_err(|_| "Error creating client")?;
 
+    let metrics_config = &config::get().metrics;
+    if metrics_config.enabled {
+        let addr = metrics_config.listen.parse()?;
+        tokio::spawn(async move {
+            if let Err(why) = metrics::serve(addr).await {
+                tracing::error!("Metrics server error: {:?}", why);
+            }
+        });
+    }
+
+    tokio::spawn(ddragon::refresh_periodically());
+    trackers::spawn(client.http.clone());
+
+    let shard_manager = client.shard_manager.clone();
+    let cache = client.cache.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(15));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown::requested() => break,
+            }
+            let total = cache.shard_count();
+            let guilds = cache.guilds();
+            for (shard_id, runner) in shard_manager.runners.lock().await.iter() {
+                if let Some(latency) = runner.latency {
+                    metrics::set_gateway_latency(shard_id.0, latency);
+                }
+                metrics::set_shard_stage(shard_id.0, runner.stage.to_string());
+                let served = guilds.iter().filter(|guild| serenity::utils::shard_id(**guild, total) == shard_id.0).count();
+                metrics::set_shard_guilds(shard_id.0, served);
+            }
+            metrics::set_guilds(guilds.len());
+        }
+    });
+
+
+    let shard_manager = client.shard_manager.clone();
+    let stopped = tokio::spawn(async move {
+        shutdown::signal().await;
+        tracing::info!("Shutting down, waiting for running commands");
+        shutdown::request();
+
+        let unfinished = shutdown::drain(SHUTDOWN_TIMEOUT).await;
+        if unfinished > 0 {
+            tracing::warn!("{} commands did not finish within {:?}", unfinished, SHUTDOWN_TIMEOUT);
+        }
+
+        if let Err(why) = SETTINGS.flush() {
+            tracing::error!("Error saving guild settings: {:?}", why);
+        }
+
+        shard_manager.shutdown_all().await;
+    });
 
-    client.start().await.map_err(|err| {
-        eprintln!("Client error: {:?}", err);
-        "Client error"
-    })?;
+    // `shutdown_all` only ends `start` once a shard has connected, so also stop when the shutdown is done
+    tokio::select! {
+        result = start(&mut client) => result.map_err(|err| {
+            tracing::error!("Client error: {:?}", err);
+            "Client error"
+        })?,
+        _ = stopped => {}
+    }
 
+    tracing::info!("Shut down cleanly");
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,155 @@
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec, register_gauge_vec,
+    Encoder, GaugeVec, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+lazy_static! {
+    static ref COMMANDS: IntCounterVec =
+        register_int_counter_vec!("kir_commands_total", "Commands handled, by command name", &["command"]).unwrap();
+    static ref ERRORS: IntCounterVec =
+        register_int_counter_vec!("kir_errors_total", "Errors, by kind", &["kind"]).unwrap();
+    static ref RIOT_LATENCY: HistogramVec = register_histogram_vec!(
+        "kir_riot_request_duration_seconds",
+        "Riot API request latency, by endpoint",
+        &["endpoint"],
+        vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0]
+    )
+    .unwrap();
+    static ref RIOT_RESPONSES: IntCounterVec = register_int_counter_vec!(
+        "kir_riot_responses_total",
+        "Riot API responses, by endpoint and status code",
+        &["endpoint", "status"]
+    )
+    .unwrap();
+    static ref CACHE_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "kir_cache_requests_total",
+        "Cache lookups, by cache and result (hit or miss)",
+        &["cache", "result"]
+    )
+    .unwrap();
+    static ref GATEWAY_LATENCY: GaugeVec = register_gauge_vec!(
+        "kir_gateway_latency_seconds",
+        "Heartbeat latency, by shard",
+        &["shard"]
+    )
+    .unwrap();
+    static ref RIOT_QUEUE_DEPTH: IntGaugeVec = register_int_gauge_vec!(
+        "kir_riot_queue_depth",
+        "Riot API requests waiting for the rate limiter, by priority",
+        &["priority"]
+    )
+    .unwrap();
+    static ref GUILDS: IntGauge = register_int_gauge!("kir_guilds", "Guilds the bot is connected to").unwrap();
+    static ref SHARD_GUILDS: IntGaugeVec =
+        register_int_gauge_vec!("kir_shard_guilds", "Guilds served, by shard", &["shard"]).unwrap();
+
+    /// Connection stage of every shard, as reported by the gateway.
+    static ref SHARD_STAGES: Mutex<BTreeMap<u32, String>> = Mutex::new(BTreeMap::new());
+}
+
+pub fn record_command(command: &str) {
+    COMMANDS.with_label_values(&[command]).inc();
+}
+
+pub fn record_error(kind: &str) {
+    ERRORS.with_label_values(&[kind]).inc();
+}
+
+/// `status` is the HTTP status code, or `None` if no response arrived.
+pub fn record_riot_request(endpoint: &str, elapsed: Duration, status: Option<u16>) {
+    let status = status.map(|status| status.to_string()).unwrap_or_else(|| "none".to_string());
+    RIOT_LATENCY.with_label_values(&[endpoint]).observe(elapsed.as_secs_f64());
+    RIOT_RESPONSES.with_label_values(&[endpoint, &status]).inc();
+}
+
+pub fn record_cache(cache: &str, hit: bool) {
+    CACHE_REQUESTS.with_label_values(&[cache, if hit { "hit" } else { "miss" }]).inc();
+}
+
+pub fn set_riot_queue_depth(priority: &str, depth: usize) {
+    RIOT_QUEUE_DEPTH.with_label_values(&[priority]).set(depth as i64);
+}
+
+pub fn set_gateway_latency(shard: u32, latency: Duration) {
+    GATEWAY_LATENCY.with_label_values(&[&shard.to_string()]).set(latency.as_secs_f64());
+}
+
+pub fn set_guilds(count: usize) {
+    GUILDS.set(count as i64);
+}
+
+pub fn set_shard_guilds(shard: u32, count: usize) {
+    SHARD_GUILDS.with_label_values(&[&shard.to_string()]).set(count as i64);
+}
+
+pub fn set_shard_stage(shard: u32, stage: String) {
+    SHARD_STAGES.lock().unwrap().insert(shard, stage);
+}
+
+/// Healthy once at least one shard has connected and every known shard is connected.
+fn health() -> (bool, String) {
+    let stages = SHARD_STAGES.lock().unwrap();
+    let healthy = !stages.is_empty() && stages.values().all(|stage| stage == "connected");
+    let body = stages
+        .iter()
+        .map(|(shard, stage)| format!("shard {}: {}\n", shard, stage))
+        .collect::<String>();
+
+    (healthy, if body.is_empty() { "no shards connected\n".to_string() } else { body })
+}
+
+fn render() -> String {
+    let mut buffer = Vec::new();
+    if let Err(why) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        tracing::error!("Error encoding metrics: {:?}", why);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// Serves `/metrics` and `/healthz` until the process exits.
+pub async fn serve(addr: SocketAddr) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!("Metrics server listening on {}", addr);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(async move {
+            if let Err(why) = respond(stream).await {
+                tracing::debug!("Error serving metrics request: {:?}", why);
+            }
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream) -> std::io::Result<()> {
+    let mut buffer = [0; 1024];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+
+    let (status, content_type, body) = match path {
+        "/metrics" => ("200 OK", "text/plain; version=0.0.4", render()),
+        "/healthz" => match health() {
+            (true, body) => ("200 OK", "text/plain", body),
+            (false, body) => ("503 Service Unavailable", "text/plain", body),
+        },
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -0,0 +1,164 @@
+use serenity::model::channel::Message;
+use serenity::model::guild::Member;
+use serenity::model::id::{GuildId, RoleId, UserId};
+use serenity::prelude::*;
+
+use crate::config;
+use crate::settings::SETTINGS;
+
+/// Who may run a command. Levels are ordered, so a higher level implies the lower ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PermissionLevel {
+    Everyone,
+    /// Members with Manage Guild or the guild's configured admin role.
+    Admin,
+    /// Bot owners listed in `discord.owner_ids`.
+    Owner,
+}
+
+impl PermissionLevel {
+    pub fn denied_message(&self) -> &'static str {
+        match self {
+            PermissionLevel::Everyone => "You are not allowed to use this command.",
+            PermissionLevel::Admin => {
+                "You need the **Manage Server** permission or the bot admin role to use this command."
+            }
+            PermissionLevel::Owner => "Only the bot owners can use this command.",
+        }
+    }
+}
+
+pub fn is_owner(user_id: UserId) -> bool {
+    config::get().discord.owner_ids.contains(&user_id.get())
+}
+
+fn has_admin_role(guild_id: GuildId, roles: &[RoleId]) -> bool {
+    SETTINGS
+        .get(Some(guild_id))
+        .admin_role
+        .is_some_and(|role| roles.contains(&RoleId::new(role)))
+}
+
+/// Resolves the permission level of a message author. Outside of guilds only owners rank above `Everyone`.
+pub async fn message_level(ctx: &Context, msg: &Message) -> PermissionLevel {
+    if is_owner(msg.author.id) {
+        return PermissionLevel::Owner;
+    }
+
+    let Some(guild_id) = msg.guild_id else {
+        return PermissionLevel::Everyone;
+    };
+
+    if let Some(level) = cached_level(ctx, guild_id, msg) {
+        return level;
+    }
+
+    let member = match msg.member(ctx).await {
+        Ok(member) => member,
+        Err(why) => {
+            tracing::error!("Error fetching member: {:?}", why);
+            return PermissionLevel::Everyone;
+        }
+    };
+
+    if has_admin_role(guild_id, &member.roles) {
+        return PermissionLevel::Admin;
+    }
+
+    match guild_id.to_partial_guild(&ctx.http).await {
+        Ok(guild) if guild.member_permissions(&member).manage_guild() => PermissionLevel::Admin,
+        Ok(_) => PermissionLevel::Everyone,
+        Err(why) => {
+            tracing::error!("Error fetching guild: {:?}", why);
+            PermissionLevel::Everyone
+        }
+    }
+}
+
+/// The level from the roles Discord sends along with guild messages and the cached guild, without
+/// any requests. `None` if either is missing, e.g. while the guild is not cached yet.
+fn cached_level(ctx: &Context, guild_id: GuildId, msg: &Message) -> Option<PermissionLevel> {
+    let member = msg.member.as_deref()?;
+    if has_admin_role(guild_id, &member.roles) {
+        return Some(PermissionLevel::Admin);
+    }
+
+    let guild = ctx.cache.guild(guild_id)?;
+    // Every member has the @everyone role, whose id is the guild's
+    let everyone = RoleId::new(guild_id.get());
+    let manage_guild = guild.owner_id == msg.author.id
+        || member
+            .roles
+            .iter()
+            .chain([&everyone])
+            .filter_map(|role_id| guild.roles.get(role_id))
+            .any(|role| role.permissions.administrator() || role.permissions.manage_guild());
+
+    Some(if manage_guild { PermissionLevel::Admin } else { PermissionLevel::Everyone })
+}
+
+/// Resolves the permission level of an interaction user from the member Discord sends along.
+/// Outside of guilds only owners rank above `Everyone`.
+pub fn interaction_level(user_id: UserId, guild_id: Option<GuildId>, member: Option<&Member>) -> PermissionLevel {
+    if is_owner(user_id) {
+        return PermissionLevel::Owner;
+    }
+
+    let (Some(guild_id), Some(member)) = (guild_id, member) else {
+        return PermissionLevel::Everyone;
+    };
+
+    let manage_guild = member.permissions.is_some_and(|permissions| permissions.manage_guild());
+
+    if manage_guild || has_admin_role(guild_id, &member.roles) {
+        PermissionLevel::Admin
+    } else {
+        PermissionLevel::Everyone
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::model::Permissions;
+
+    fn member(roles: &[u64], permissions: Permissions) -> Member {
+        let mut member = Member::default();
+        member.roles = roles.iter().copied().map(RoleId::new).collect();
+        member.permissions = Some(permissions);
+        member
+    }
+
+    #[test]
+    fn levels_are_ordered() {
+        assert!(PermissionLevel::Everyone < PermissionLevel::Admin);
+        assert!(PermissionLevel::Admin < PermissionLevel::Owner);
+    }
+
+    #[test]
+    fn the_admin_role_grants_admin() {
+        let guild_id = GuildId::new(2601);
+        SETTINGS.update(guild_id, |settings| settings.admin_role = Some(77));
+
+        assert!(has_admin_role(guild_id, &[RoleId::new(5), RoleId::new(77)]));
+        assert!(!has_admin_role(guild_id, &[RoleId::new(5)]));
+        assert!(!has_admin_role(GuildId::new(2602), &[RoleId::new(77)]));
+
+        let user_id = UserId::new(1);
+        let level = |roles| interaction_level(user_id, Some(guild_id), Some(&member(roles, Permissions::empty())));
+        assert_eq!(level(&[77]), PermissionLevel::Admin);
+        assert_eq!(level(&[5]), PermissionLevel::Everyone);
+    }
+
+    #[test]
+    fn manage_server_grants_admin_in_guilds_only() {
+        let (user_id, guild_id) = (UserId::new(1), Some(GuildId::new(2603)));
+        let manager = member(&[], Permissions::MANAGE_GUILD);
+        let member = member(&[], Permissions::SEND_MESSAGES);
+
+        assert_eq!(interaction_level(user_id, guild_id, Some(&manager)), PermissionLevel::Admin);
+        assert_eq!(interaction_level(user_id, guild_id, Some(&member)), PermissionLevel::Everyone);
+        assert_eq!(interaction_level(user_id, None, Some(&manager)), PermissionLevel::Everyone);
+        assert_eq!(interaction_level(user_id, guild_id, None), PermissionLevel::Everyone);
+    }
+}
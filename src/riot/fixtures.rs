@@ -0,0 +1,102 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Body Riot sends with a 404, which riven turns into `None` for lookups that may find nothing.
+const NOT_FOUND: &str = r#"{"status":{"message":"Data not found","status_code":404}}"#;
+
+/// Serves recorded Riot responses from `dir` on a local port and returns its address.
+///
+/// A request for `https://<route>.api.riotgames.com/<path>` is answered with the file
+/// `<dir>/<route>/<path>.json`, ignoring the query string, or with a 404 if there is none. For example
+/// `account-v1.getByRiotId` for `Faker#KR1` reads `asia/riot/account/v1/accounts/by-riot-id/Faker/KR1.json`.
+pub async fn serve(dir: PathBuf) -> std::io::Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tracing::info!("Serving Riot fixtures from {} on {}", dir.display(), addr);
+
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(why) => {
+                    tracing::error!("Error accepting fixture request: {:?}", why);
+                    continue;
+                }
+            };
+            let dir = dir.clone();
+            tokio::spawn(async move {
+                if let Err(why) = respond(&dir, stream).await {
+                    tracing::debug!("Error serving fixture request: {:?}", why);
+                }
+            });
+        }
+    });
+
+    Ok(addr)
+}
+
+async fn respond(dir: &Path, mut stream: TcpStream) -> std::io::Result<()> {
+    let mut buffer = [0; 4096];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let target = request.split_whitespace().nth(1).unwrap_or("/");
+    let path = target.split('?').next().unwrap_or_default();
+
+    let file = fixture_path(dir, path);
+    let (status, body) = match file.as_ref().map(std::fs::read_to_string) {
+        Some(Ok(body)) => ("200 OK", body),
+        _ => {
+            tracing::debug!("No fixture for {}", path);
+            ("404 Not Found", NOT_FOUND.to_string())
+        }
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Maps a request path to its fixture file, refusing anything that could escape `dir`.
+fn fixture_path(dir: &Path, path: &str) -> Option<PathBuf> {
+    let mut file = dir.to_path_buf();
+    for (i, segment) in path.split('/').filter(|segment| !segment.is_empty()).enumerate() {
+        let mut segment = percent_decode(segment)?;
+        // riven sends the route in upper case, the fixture directories use the host name
+        if i == 0 {
+            segment = segment.to_lowercase();
+        }
+        if segment == "." || segment == ".." || segment.contains(['/', '\\']) {
+            return None;
+        }
+        file.push(segment);
+    }
+    file.set_extension(match file.extension() {
+        Some(extension) => format!("{}.json", extension.to_string_lossy()),
+        None => "json".to_string(),
+    });
+    Some(file)
+}
+
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = segment.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
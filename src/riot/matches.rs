@@ -0,0 +1,135 @@
+//! Match history with an in-memory cache of match details. Finished matches never change, so a match
+//! is kept for `[cache] match_ttl_secs` and repeated lookups of the same players cost one request for
+//! the match ids.
+
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use lazy_static::lazy_static;
+use riven::consts::PlatformRoute;
+use riven::models::match_v5::{Match, Participant};
+use riven::RiotApi;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{config, metrics, riot};
+
+/// Match details requested at a time. Every request waits for the rate limits on its own, so
+/// sending them all at once only makes more of them give up.
+const CONCURRENT_FETCHES: usize = 10;
+
+/// The most match ids match-v5 returns per request.
+pub const MAX_COUNT: i32 = 100;
+
+lazy_static! {
+    static ref CACHE: Mutex<HashMap<String, (Instant, Arc<Match>)>> = Mutex::new(HashMap::new());
+}
+
+/// Which matches to list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Any,
+    /// Solo/Duo and Flex.
+    Ranked,
+}
+
+/// A player's latest matches, newest first.
+pub struct History {
+    pub games: Vec<Arc<Match>>,
+    /// Matches that failed to load, e.g. because the Riot API was busy, and are left out of `games`.
+    pub skipped: usize,
+}
+
+impl History {
+    /// Tells users that statistics over `games` are missing some matches.
+    pub fn skipped_note(&self) -> Option<String> {
+        match self.skipped {
+            0 => None,
+            1 => Some("1 match could not be loaded and is left out.".to_string()),
+            skipped => Some(format!("{} matches could not be loaded and are left out.", skipped)),
+        }
+    }
+}
+
+/// The player's latest `count` matches of `kind`. Matches that fail to load are left out and
+/// counted, unless none load at all. Only call this after deferring the reply, since loading many
+/// matches may wait on the rate limits.
+pub async fn recent(riot_api: &RiotApi, platform: PlatformRoute, puuid: &str, kind: Kind, count: i32) -> Result<History> {
+    let region = platform.to_regional();
+    let count = count.clamp(1, MAX_COUNT);
+    let match_type = match kind {
+        Kind::Any => None,
+        Kind::Ranked => Some("ranked"),
+    };
+
+    let match_ids = riot::deferred(
+        region,
+        "match-v5.getMatchIdsByPUUID",
+        riot_api.match_v5().get_match_ids_by_puuid(region, puuid, Some(count), None, None, None, None, match_type),
+    )
+    .await?;
+
+    // Collecting the futures first keeps the stream free of closures, which the command futures
+    // need to stay `Send`
+    let fetches: Vec<_> = match_ids.iter().take(count as usize).map(|match_id| get(riot_api, platform, match_id)).collect();
+    let results: Vec<Result<Option<Arc<Match>>>> = stream::iter(fetches).buffered(CONCURRENT_FETCHES).collect().await;
+
+    let mut history = History { games: Vec::new(), skipped: 0 };
+    let mut first_error = None;
+    for (result, match_id) in results.into_iter().zip(&match_ids) {
+        match result {
+            Ok(game) => history.games.extend(game),
+            Err(why) => {
+                tracing::warn!(match_id = %match_id, "Error fetching match: {:#}", why);
+                history.skipped += 1;
+                first_error.get_or_insert(why);
+            }
+        }
+    }
+
+    match first_error {
+        Some(why) if history.games.is_empty() => Err(why),
+        _ => Ok(history),
+    }
+}
+
+/// A match by id, from the cache when possible. Only call this after deferring the reply.
+pub async fn get(riot_api: &RiotApi, platform: PlatformRoute, match_id: &str) -> Result<Option<Arc<Match>>> {
+    let ttl = Duration::from_secs(config::get().cache.match_ttl_secs);
+    let cached = CACHE
+        .lock()
+        .unwrap()
+        .get(match_id)
+        .filter(|(fetched, _)| fetched.elapsed() < ttl)
+        .map(|(_, game)| game.clone());
+    metrics::record_cache("matches", cached.is_some());
+    if cached.is_some() {
+        return Ok(cached);
+    }
+
+    let region = platform.to_regional();
+    let Some(game) = riot::deferred(region, "match-v5.getMatch", riot_api.match_v5().get_match(region, match_id)).await? else {
+        return Ok(None);
+    };
+    let game = Arc::new(game);
+
+    let max_entries = config::get().cache.match_cache_entries;
+    let mut cache = CACHE.lock().unwrap();
+    if cache.len() >= max_entries {
+        cache.retain(|_, (fetched, _)| fetched.elapsed() < ttl);
+        if cache.len() >= max_entries {
+            // Still full of live entries, so make room by dropping the oldest
+            if let Some(oldest) = cache.iter().min_by_key(|(_, (fetched, _))| *fetched).map(|(id, _)| id.clone()) {
+                cache.remove(&oldest);
+            }
+        }
+    }
+    cache.insert(match_id.to_string(), (Instant::now(), game.clone()));
+
+    Ok(Some(game))
+}
+
+/// The player's entry in a match.
+pub fn participant<'a>(game: &'a Match, puuid: &str) -> Option<&'a Participant> {
+    game.info.participants.iter().find(|participant| participant.puuid == puuid)
+}
@@ -0,0 +1,134 @@
+use anyhow::Result;
+use lazy_static::lazy_static;
+use riven::{RiotApi, RiotApiConfig};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+pub mod fixtures;
+pub mod matches;
+pub mod player;
+pub mod queue;
+pub mod region;
+
+use queue::{Priority, RiotQueue};
+
+use crate::{config, metrics};
+
+/// Commands defer their reply before any Riot lookup, so they are past Discord's 3-second window.
+/// Deferred replies can be filled in for 15 minutes, but users should not wait much longer than this.
+const DEFERRED_MAX_WAIT: Duration = Duration::from_secs(15);
+
+lazy_static! {
+    pub static ref QUEUE: RiotQueue = RiotQueue::new(&config::get().riot.app_rate_limit);
+}
+
+static RIOT_API: OnceLock<RiotApi> = OnceLock::new();
+
+/// The Riot client shared by every command, created on first use from the configured API key.
+pub fn api() -> Result<&'static RiotApi> {
+    if let Some(riot_api) = RIOT_API.get() {
+        return Ok(riot_api);
+    }
+
+    let api_key = &config::get().riot.api_key;
+    if api_key.is_empty() {
+        anyhow::bail!("riot.api_key is not configured");
+    }
+    Ok(RIOT_API.get_or_init(|| RiotApi::new(api_key)))
+}
+
+/// Answers every Riot request from the recorded responses in `dir` instead of the real API.
+/// Must run before the first call to [`api`].
+pub async fn use_fixtures(dir: PathBuf) -> Result<()> {
+    let addr = fixtures::serve(dir).await?;
+    let config = RiotApiConfig::with_key("fixtures")
+        .set_base_url(format!("http://{}/{{}}", addr))
+        .set_retries(0);
+
+    RIOT_API
+        .set(RiotApi::new(config))
+        .map_err(|_| anyhow::anyhow!("the Riot client was created before the fixtures were loaded"))
+}
+
+/// The reply for a failed Riot lookup: when to try again if the API is busy, otherwise `fallback`,
+/// e.g. `Failed to fetch the server status.`
+pub fn error_reply(why: &anyhow::Error, fallback: &str) -> String {
+    match why.downcast_ref::<queue::RiotBusy>() {
+        Some(busy) => busy.to_string(),
+        None => fallback.to_string(),
+    }
+}
+
+/// Serves the repository's `fixtures` for the whole test run. The server gets a runtime of its own,
+/// since every test runs on a runtime that ends with it.
+#[cfg(test)]
+pub fn use_test_fixtures() {
+    static STARTED: std::sync::Once = std::sync::Once::new();
+    STARTED.call_once(|| {
+        let (started, ready) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async {
+                let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+                started.send(use_fixtures(dir).await.map_err(|why| format!("{:#}", why))).unwrap();
+                std::future::pending::<()>().await
+            })
+        });
+        ready.recv().unwrap().expect("serving the Riot fixtures");
+    });
+}
+
+/// Sends a request for a command that has already deferred its reply to `method` on `route`, ahead
+/// of background requests. Fails with [`queue::RiotBusy`] instead of waiting past [`DEFERRED_MAX_WAIT`].
+pub async fn deferred<T, F>(route: impl Into<&'static str>, method: &'static str, request: F) -> Result<T>
+where
+    F: Future<Output = riven::Result<T>>,
+{
+    let route = route.into();
+    QUEUE.acquire(Priority::Interactive, route, method, Some(DEFERRED_MAX_WAIT)).await?;
+    timed(route, method, request).await
+}
+
+/// Sends a background request to `method` on `route`, waiting as long as the rate limits require.
+pub async fn background<T, F>(route: impl Into<&'static str>, method: &'static str, request: F) -> Result<T>
+where
+    F: Future<Output = riven::Result<T>>,
+{
+    let route = route.into();
+    QUEUE.acquire(Priority::Background, route, method, None).await?;
+    timed(route, method, request).await
+}
+
+/// Logs how long the request took and how it ended, inside the span of the calling command.
+async fn timed<T, F>(route: &'static str, method: &'static str, request: F) -> Result<T>
+where
+    F: Future<Output = riven::Result<T>>,
+{
+    let started = Instant::now();
+    let result = request.await;
+    let elapsed = started.elapsed();
+    let elapsed_ms = elapsed.as_millis() as u64;
+
+    match &result {
+        Ok(_) => {
+            metrics::record_riot_request(method, elapsed, Some(200));
+            tracing::debug!(endpoint = method, route, elapsed_ms, "Riot request");
+        }
+        Err(why) => {
+            metrics::record_riot_request(method, elapsed, why.status_code().map(|status| status.as_u16()));
+            metrics::record_error("riot_api");
+            tracing::warn!(
+                endpoint = method,
+                route,
+                elapsed_ms,
+                status = why.status_code().map(|status| status.as_u16()),
+                "Riot request failed: {}",
+                why
+            );
+        }
+    }
+
+    Ok(result?)
+}
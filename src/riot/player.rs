@@ -0,0 +1,356 @@
+//! Player lookups shared by the commands that show statistics: the account behind a Riot ID, ranks,
+//! champion masteries and form over the latest matches.
+
+use anyhow::{Context as _, Result};
+use riven::consts::{Division, PlatformRoute, QueueType, Tier};
+use riven::models::account_v1::Account;
+use riven::models::champion_mastery_v4::ChampionMastery;
+use riven::models::match_v5::Participant;
+use riven::RiotApi;
+
+use crate::riot::{self, matches::{self, Kind}};
+
+/// Splits `Name#TAG`, keeping spaces inside the name. The tag is empty if there is none.
+pub fn parse_riot_id(input: &str) -> (&str, &str) {
+    match input.trim().split_once('#') {
+        Some((game_name, tag_line)) => (game_name.trim(), tag_line.trim()),
+        None => (input.trim(), ""),
+    }
+}
+
+/// Splits input holding several Riot IDs, such as `Hide on bush#KR1 Chovy#KR1`. A tag ends at the
+/// first space, so names may contain spaces but tags may not.
+pub fn split_riot_ids(input: &str) -> Vec<&str> {
+    let mut ids = Vec::new();
+    let mut rest = input.trim();
+    while !rest.is_empty() {
+        let end = match rest.find('#') {
+            Some(hash) => rest[hash..].find(char::is_whitespace).map_or(rest.len(), |space| hash + space),
+            None => rest.len(),
+        };
+        ids.push(rest[..end].trim());
+        rest = rest[end..].trim_start();
+    }
+    ids
+}
+
+/// The account for a Riot ID, or `None` if there is no such player.
+pub async fn account(riot_api: &RiotApi, platform: PlatformRoute, game_name: &str, tag_line: &str) -> Result<Option<Account>> {
+    tracing::debug!("Fetching account for {}#{}", game_name, tag_line);
+    riot::deferred(
+        platform.to_regional(),
+        "account-v1.getByRiotId",
+        riot_api.account_v1().get_by_riot_id(platform.to_regional(), game_name, tag_line),
+    )
+    .await
+    .context("fetching account")
+}
+
+/// `Name#TAG` as Riot spells it.
+pub fn riot_id(account: &Account) -> String {
+    format!(
+        "{}#{}",
+        account.game_name.as_deref().unwrap_or_default(),
+        account.tag_line.as_deref().unwrap_or_default()
+    )
+}
+
+/// A player's standing in one ranked queue.
+pub struct QueueRank {
+    pub queue: QueueType,
+    /// e.g. `DIAMOND II: 75 LP`, without the division for apex tiers.
+    pub rank: String,
+    /// The rank as one number for comparing players, see [`rating`]. `None` while unranked.
+    pub rating: Option<i32>,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+impl QueueRank {
+    pub fn queue_name(&self) -> &'static str {
+        queue_name(&self.queue)
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        win_rate(self.wins, self.losses)
+    }
+}
+
+pub struct Summary {
+    pub level: i64,
+    /// Solo/Duo first, then Flex, then anything else Riot reports.
+    pub ranks: Vec<QueueRank>,
+}
+
+impl Summary {
+    pub fn rank(&self, queue: &QueueType) -> Option<&QueueRank> {
+        self.ranks.iter().find(|rank| rank.queue == *queue)
+    }
+
+    /// The rank to compare players by: Solo/Duo, or Flex for players without a Solo/Duo rating.
+    pub fn rated(&self) -> Option<&QueueRank> {
+        [QueueType::RANKED_SOLO_5x5, QueueType::RANKED_FLEX_SR]
+            .iter()
+            .find_map(|queue| self.rank(queue).filter(|rank| rank.rating.is_some()))
+    }
+
+    pub fn total_wins(&self) -> u32 {
+        self.ranks.iter().map(|rank| rank.wins).sum()
+    }
+
+    pub fn total_losses(&self) -> u32 {
+        self.ranks.iter().map(|rank| rank.losses).sum()
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        win_rate(self.total_wins(), self.total_losses())
+    }
+}
+
+/// The name players use for a ranked queue.
+pub fn queue_name(queue: &QueueType) -> &'static str {
+    match queue {
+        QueueType::RANKED_SOLO_5x5 => "Solo/Duo",
+        QueueType::RANKED_FLEX_SR => "Flex",
+        queue => queue.into(),
+    }
+}
+
+/// LP above Iron IV: 100 per division and 400 per tier, the way LP adds up on the ladder. Master and
+/// above share one continuous LP scale starting at 2800.
+pub fn rating(tier: Tier, division: Option<Division>, league_points: i32) -> Option<i32> {
+    let tier = match tier {
+        Tier::IRON => 0,
+        Tier::BRONZE => 1,
+        Tier::SILVER => 2,
+        Tier::GOLD => 3,
+        Tier::PLATINUM => 4,
+        Tier::EMERALD => 5,
+        Tier::DIAMOND => 6,
+        Tier::UNRANKED => return None,
+        _ => return Some(7 * 400 + league_points),
+    };
+    // Division IV is the bottom of a tier
+    let division = 4 - division.map_or(4, |division| (division as i32).min(4));
+    Some(tier * 400 + division * 100 + league_points)
+}
+
+/// Wins as a percentage of games, rounded; 0 without games.
+pub fn win_rate(wins: u32, losses: u32) -> f64 {
+    let games = wins + losses;
+    if games == 0 {
+        return 0.0;
+    }
+    (wins as f64 / games as f64 * 100.0).round()
+}
+
+/// Summoner level and ranked standings.
+pub async fn summary(riot_api: &RiotApi, platform: PlatformRoute, puuid: &str) -> Result<Summary> {
+    let summoner = riot::deferred(
+        platform,
+        "summoner-v4.getByPUUID",
+        riot_api.summoner_v4().get_by_puuid(platform, puuid),
+    )
+    .await?;
+
+    let leagues = riot::deferred(
+        platform,
+        "league-v4.getLeagueEntriesForSummoner",
+        riot_api.league_v4().get_league_entries_for_summoner(platform, &summoner.id),
+    )
+    .await?;
+
+    let mut ranks: Vec<QueueRank> = leagues
+        .into_iter()
+        .map(|entry| {
+            let rank = match (entry.tier, entry.rank) {
+                (Some(tier), Some(division)) if !tier.is_apex() => {
+                    format!("{} {}: {} LP", tier, division, entry.league_points)
+                }
+                (Some(tier), _) => format!("{}: {} LP", tier, entry.league_points),
+                (None, _) => "Unranked".to_string(),
+            };
+            QueueRank {
+                queue: entry.queue_type,
+                rank,
+                rating: entry.tier.and_then(|tier| rating(tier, entry.rank, entry.league_points)),
+                wins: entry.wins as u32,
+                losses: entry.losses as u32,
+            }
+        })
+        .collect();
+    let order = |queue: &QueueType| match queue {
+        QueueType::RANKED_SOLO_5x5 => 0,
+        QueueType::RANKED_FLEX_SR => 1,
+        _ => 2,
+    };
+    ranks.sort_by_key(|rank| order(&rank.queue));
+
+    Ok(Summary { level: summoner.summoner_level, ranks })
+}
+
+/// Every champion mastery, highest points first.
+pub async fn masteries(riot_api: &RiotApi, platform: PlatformRoute, puuid: &str) -> Result<Vec<ChampionMastery>> {
+    riot::deferred(
+        platform,
+        "champion-mastery-v4.getAllChampionMasteriesByPUUID",
+        riot_api.champion_mastery_v4().get_all_champion_masteries_by_puuid(platform, puuid),
+    )
+    .await
+}
+
+/// Averages over a player's latest matches.
+pub struct RecentForm {
+    pub games: usize,
+    pub wins: usize,
+    pub kills: f64,
+    pub deaths: f64,
+    pub assists: f64,
+    pub roles: Vec<RoleStats>,
+    /// Why the averages leave some matches out, if they do.
+    pub skipped_note: Option<String>,
+}
+
+/// Form over the latest `count` matches, or `None` without any.
+pub async fn recent_form(riot_api: &RiotApi, platform: PlatformRoute, puuid: &str, count: i32) -> Result<Option<RecentForm>> {
+    let history = matches::recent(riot_api, platform, puuid, Kind::Any, count).await?;
+    let participants: Vec<&Participant> = history.games.iter().filter_map(|game| matches::participant(game, puuid)).collect();
+
+    if participants.is_empty() {
+        return Ok(None);
+    }
+
+    let games = participants.len();
+    let average = |stat: fn(&Participant) -> i32| {
+        participants.iter().map(|participant| stat(participant) as f64).sum::<f64>() / games as f64
+    };
+
+    Ok(Some(RecentForm {
+        games,
+        wins: participants.iter().filter(|participant| participant.win).count(),
+        kills: average(|participant| participant.kills),
+        deaths: average(|participant| participant.deaths),
+        assists: average(|participant| participant.assists),
+        roles: roles(participants.iter().copied()),
+        skipped_note: history.skipped_note(),
+    }))
+}
+
+/// Games and wins in one position.
+pub struct RoleStats {
+    pub role: &'static str,
+    pub games: usize,
+    pub wins: usize,
+}
+
+impl RoleStats {
+    pub fn win_rate(&self) -> f64 {
+        win_rate(self.wins as u32, (self.games - self.wins) as u32)
+    }
+}
+
+/// Games per position, most played first. Modes without positions, such as ARAM, are left out.
+pub fn roles<'a>(participants: impl IntoIterator<Item = &'a Participant>) -> Vec<RoleStats> {
+    let mut roles: Vec<RoleStats> = Vec::new();
+    for participant in participants {
+        let Some(role) = role_name(&participant.team_position) else { continue };
+        let index = match roles.iter().position(|stats| stats.role == role) {
+            Some(index) => index,
+            None => {
+                roles.push(RoleStats { role, games: 0, wins: 0 });
+                roles.len() - 1
+            }
+        };
+        roles[index].games += 1;
+        roles[index].wins += participant.win as usize;
+    }
+    roles.sort_by_key(|stats| std::cmp::Reverse(stats.games));
+    roles
+}
+
+/// Fewer games say little about what someone mains.
+const MIN_VERDICT_GAMES: usize = 5;
+
+/// A short verdict on what the player mains, e.g. `Mid main` or `Top / Jungle flex`.
+pub fn main_role(roles: &[RoleStats]) -> String {
+    let total: usize = roles.iter().map(|stats| stats.games).sum();
+    if total < MIN_VERDICT_GAMES {
+        return "Not enough games".to_string();
+    }
+
+    let share = |stats: &RoleStats| stats.games as f64 / total as f64;
+    match roles {
+        [first, ..] if share(first) >= 0.6 => format!("{} main", first.role),
+        [first, second, ..] if share(first) + share(second) >= 0.7 => format!("{} / {} flex", first.role, second.role),
+        _ => "Fill".to_string(),
+    }
+}
+
+/// The name players use for a match-v5 `teamPosition`.
+pub fn role_name(position: &str) -> Option<&'static str> {
+    match position {
+        "TOP" => Some("Top"),
+        "JUNGLE" => Some("Jungle"),
+        "MIDDLE" => Some("Mid"),
+        "BOTTOM" => Some("Bot"),
+        "UTILITY" => Some("Support"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use riven::models::match_v5::Match;
+    use std::path::PathBuf;
+
+    /// A participant from a fixture match, moved to `position`.
+    fn participant(position: &str, win: bool) -> Participant {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/asia/lol/match/v5/matches/KR_7000000001.json");
+        let game: Match = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let mut participant = game.info.participants[0].clone();
+        participant.team_position = position.to_string();
+        participant.win = win;
+        participant
+    }
+
+    fn stats(games: &[(&'static str, usize)]) -> Vec<RoleStats> {
+        games.iter().map(|&(role, games)| RoleStats { role, games, wins: 0 }).collect()
+    }
+
+    #[test]
+    fn counts_games_per_position() {
+        let participants = [
+            participant("MIDDLE", true),
+            participant("TOP", false),
+            participant("MIDDLE", false),
+            participant("", true),
+            participant("MIDDLE", true),
+        ];
+        let roles = roles(&participants);
+
+        let counts: Vec<_> = roles.iter().map(|stats| (stats.role, stats.games, stats.wins)).collect();
+        assert_eq!(counts, [("Mid", 3, 2), ("Top", 1, 0)]);
+        assert_eq!(roles[0].win_rate(), 67.0);
+    }
+
+    #[test]
+    fn mains_need_three_in_five_games() {
+        assert_eq!(main_role(&stats(&[("Mid", 3), ("Top", 1), ("Bot", 1)])), "Mid main");
+        assert_eq!(main_role(&stats(&[("Mid", 5), ("Top", 3), ("Bot", 2)])), "Mid / Top flex");
+    }
+
+    #[test]
+    fn flex_needs_seven_in_ten_games_across_two_roles() {
+        assert_eq!(main_role(&stats(&[("Mid", 4), ("Top", 3), ("Bot", 3)])), "Mid / Top flex");
+        assert_eq!(main_role(&stats(&[("Mid", 4), ("Top", 2), ("Bot", 2), ("Support", 2)])), "Fill");
+        assert_eq!(main_role(&stats(&[("Mid", 5), ("Top", 5)])), "Mid / Top flex");
+    }
+
+    #[test]
+    fn few_games_give_no_verdict() {
+        assert_eq!(main_role(&[]), "Not enough games");
+        assert_eq!(main_role(&stats(&[("Mid", MIN_VERDICT_GAMES - 1)])), "Not enough games");
+        assert_eq!(main_role(&stats(&[("Mid", MIN_VERDICT_GAMES)])), "Mid main");
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Interactive requests are admitted first; background requests also leave part of every window free
+/// so a user never waits behind a poller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Interactive,
+    Background,
+}
+
+impl Priority {
+    fn label(self) -> &'static str {
+        match self {
+            Priority::Interactive => "interactive",
+            Priority::Background => "background",
+        }
+    }
+}
+
+/// Returned instead of waiting when a request would not be admitted in time.
+#[derive(Debug)]
+pub struct RiotBusy {
+    pub wait: Duration,
+}
+
+impl fmt::Display for RiotBusy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The Riot API is busy right now, try again in {}s.", self.wait.as_secs().max(1))
+    }
+}
+
+impl std::error::Error for RiotBusy {}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueueDepth {
+    pub interactive: usize,
+    pub background: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    requests: usize,
+    window: Duration,
+}
+
+/// Parses Riot's `X-App-Rate-Limit` format, e.g. `20:1,100:120`.
+pub fn parse_limits(value: &str) -> Vec<RateLimit> {
+    value
+        .split(',')
+        .filter_map(|pair| {
+            let (requests, seconds) = pair.split_once(':')?;
+            Some(RateLimit {
+                requests: requests.trim().parse().ok()?,
+                window: Duration::from_secs(seconds.trim().parse().ok()?),
+            })
+        })
+        .collect()
+}
+
+/// Method limits Riot publishes for the endpoints the bot uses.
+fn default_method_limits() -> HashMap<&'static str, Vec<RateLimit>> {
+    HashMap::from([
+        ("account-v1.getByRiotId", parse_limits("1000:60")),
+        ("summoner-v4.getByPUUID", parse_limits("1600:60")),
+        ("league-v4.getLeagueEntriesForSummoner", parse_limits("100:60")),
+        ("champion-mastery-v4.getAllChampionMasteriesByPUUID", parse_limits("20000:10,1200000:600")),
+        ("match-v5.getMatchIdsByPUUID", parse_limits("2000:10")),
+        ("match-v5.getMatch", parse_limits("2000:10")),
+        ("lol-challenges-v1.getPlayerData", parse_limits("2000:10")),
+        ("champion-v3.getChampionInfo", parse_limits("30000:10,500000:600")),
+        ("lol-status-v4.getPlatformData", parse_limits("20000:10")),
+    ])
+}
+
+/// Sliding window of the requests sent under one limit.
+#[derive(Debug)]
+struct Window {
+    limit: RateLimit,
+    sent: VecDeque<Instant>,
+}
+
+impl Window {
+    fn new(limit: RateLimit) -> Self {
+        Window { limit, sent: VecDeque::new() }
+    }
+
+    /// Time until a request fits when only `capacity` of the limit may be used.
+    fn wait(&mut self, now: Instant, capacity: usize) -> Duration {
+        while self.sent.front().is_some_and(|sent| now.duration_since(*sent) >= self.limit.window) {
+            self.sent.pop_front();
+        }
+
+        if self.sent.len() < capacity {
+            return Duration::ZERO;
+        }
+
+        let blocking = self.sent.len() - capacity;
+        self.sent
+            .get(blocking)
+            .map(|sent| self.limit.window.saturating_sub(now.duration_since(*sent)))
+            .unwrap_or(self.limit.window)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Keyed by route, since Riot counts limits per platform or region.
+    app: HashMap<&'static str, Vec<Window>>,
+    methods: HashMap<(&'static str, &'static str), Vec<Window>>,
+}
+
+pub struct RiotQueue {
+    app_limits: Vec<RateLimit>,
+    method_limits: HashMap<&'static str, Vec<RateLimit>>,
+    /// Share of every window background requests may use; the rest is kept for interactive ones.
+    background_share: f64,
+    state: Mutex<State>,
+    interactive_waiting: AtomicUsize,
+    background_waiting: AtomicUsize,
+}
+
+/// Decrements a waiting counter when the request leaves the queue, however it leaves, and keeps the
+/// depth metric in step with it.
+struct Waiting<'a> {
+    counter: &'a AtomicUsize,
+    priority: Priority,
+}
+
+impl<'a> Waiting<'a> {
+    fn enter(counter: &'a AtomicUsize, priority: Priority) -> Self {
+        let depth = counter.fetch_add(1, Ordering::SeqCst) + 1;
+        crate::metrics::set_riot_queue_depth(priority.label(), depth);
+        Waiting { counter, priority }
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let depth = self.counter.fetch_sub(1, Ordering::SeqCst) - 1;
+        crate::metrics::set_riot_queue_depth(self.priority.label(), depth);
+    }
+}
+
+impl RiotQueue {
+    /// Creates a queue for a key with the given application limits, e.g. a development key's `20:1,100:120`.
+    pub fn new(app_rate_limit: &str) -> Self {
+        let app_limits = parse_limits(app_rate_limit);
+
+        RiotQueue {
+            app_limits,
+            method_limits: default_method_limits(),
+            background_share: 0.5,
+            state: Mutex::new(State::default()),
+            interactive_waiting: AtomicUsize::new(0),
+            background_waiting: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn depth(&self) -> QueueDepth {
+        QueueDepth {
+            interactive: self.interactive_waiting.load(Ordering::SeqCst),
+            background: self.background_waiting.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Waits until a request to `method` on `route` fits into every rate limit and records it.
+    /// Fails with the estimated wait when that would take longer than `max_wait`.
+    pub async fn acquire(
+        &self,
+        priority: Priority,
+        route: &'static str,
+        method: &'static str,
+        max_wait: Option<Duration>,
+    ) -> Result<(), RiotBusy> {
+        let started = Instant::now();
+        let counter = match priority {
+            Priority::Interactive => &self.interactive_waiting,
+            Priority::Background => &self.background_waiting,
+        };
+        let _waiting = Waiting::enter(counter, priority);
+
+        loop {
+            let wait = self.try_admit(priority, route, method).await;
+            if wait.is_zero() {
+                return Ok(());
+            }
+
+            if let Some(max_wait) = max_wait {
+                if started.elapsed() + wait > max_wait {
+                    let depth = self.depth();
+                    tracing::warn!(
+                        interactive = depth.interactive,
+                        background = depth.background,
+                        "Riot queue full for {} on {}",
+                        method,
+                        route
+                    );
+                    crate::metrics::record_error("riot_busy");
+                    return Err(RiotBusy { wait });
+                }
+            }
+
+            tracing::debug!("Waiting {:?} for {} on {}, depth {:?}", wait, method, route, self.depth());
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Records the request and returns zero if it fits, otherwise returns how long to wait.
+    async fn try_admit(&self, priority: Priority, route: &'static str, method: &'static str) -> Duration {
+        // Background work yields to anyone waiting interactively
+        if priority == Priority::Background && self.interactive_waiting.load(Ordering::SeqCst) > 0 {
+            return Duration::from_millis(250);
+        }
+
+        let share = match priority {
+            Priority::Interactive => 1.0,
+            Priority::Background => self.background_share,
+        };
+        let capacity = |limit: RateLimit| ((limit.requests as f64 * share).floor() as usize).max(1);
+
+        let now = Instant::now();
+        let mut state = self.state.lock().await;
+        let State { app, methods } = &mut *state;
+
+        let app_windows = app
+            .entry(route)
+            .or_insert_with(|| self.app_limits.iter().copied().map(Window::new).collect());
+        let method_windows = methods.entry((route, method)).or_insert_with(|| {
+            self.method_limits.get(method).into_iter().flatten().copied().map(Window::new).collect()
+        });
+
+        let wait = app_windows
+            .iter_mut()
+            .chain(method_windows.iter_mut())
+            .map(|window| {
+                let capacity = capacity(window.limit);
+                window.wait(now, capacity)
+            })
+            .max()
+            .unwrap_or(Duration::ZERO);
+
+        if wait.is_zero() {
+            for window in app_windows.iter_mut().chain(method_windows.iter_mut()) {
+                window.sent.push_back(now);
+            }
+        }
+
+        wait
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_free_up_as_requests_slide_out() {
+        let now = Instant::now();
+        let mut window = Window::new(parse_limits("2:10")[0]);
+        window.sent.extend([now - Duration::from_secs(9), now - Duration::from_secs(5)]);
+
+        assert_eq!(window.wait(now, 2), Duration::from_secs(1));
+        // With half the limit, the newer request has to expire as well
+        assert_eq!(window.wait(now, 1), Duration::from_secs(5));
+        assert_eq!(window.wait(now + Duration::from_secs(1), 2), Duration::ZERO);
+        assert_eq!(window.sent.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn background_requests_leave_room_for_interactive_ones() {
+        let queue = RiotQueue::new("4:60");
+        for _ in 0..2 {
+            assert!(queue.try_admit(Priority::Background, "kr", "test").await.is_zero());
+        }
+        assert!(!queue.try_admit(Priority::Background, "kr", "test").await.is_zero());
+        for _ in 0..2 {
+            assert!(queue.try_admit(Priority::Interactive, "kr", "test").await.is_zero());
+        }
+        assert!(!queue.try_admit(Priority::Interactive, "kr", "test").await.is_zero());
+        // Other routes have limits of their own
+        assert!(queue.try_admit(Priority::Background, "euw1", "test").await.is_zero());
+    }
+
+    #[tokio::test]
+    async fn background_requests_yield_while_interactive_ones_wait() {
+        let queue = RiotQueue::new("100:60");
+        let _waiting = Waiting::enter(&queue.interactive_waiting, Priority::Interactive);
+        assert_eq!(queue.try_admit(Priority::Background, "kr", "test").await, Duration::from_millis(250));
+        assert!(queue.try_admit(Priority::Interactive, "kr", "test").await.is_zero());
+    }
+
+    #[tokio::test]
+    async fn busy_queues_estimate_the_wait() {
+        let queue = RiotQueue::new("1:10");
+        queue.acquire(Priority::Interactive, "kr", "test", Some(Duration::from_secs(1))).await.unwrap();
+
+        let busy = queue.acquire(Priority::Interactive, "kr", "test", Some(Duration::from_secs(1))).await.unwrap_err();
+        assert!(busy.wait > Duration::from_secs(9) && busy.wait <= Duration::from_secs(10), "{:?}", busy.wait);
+        assert!(busy.to_string().starts_with("The Riot API is busy right now, try again in"), "{}", busy);
+        assert_eq!(queue.depth().interactive, 0);
+    }
+}
@@ -0,0 +1,159 @@
+use riven::consts::PlatformRoute;
+use std::fmt;
+
+use crate::utils;
+
+/// Every region users can pick, with the name shown to them and the other names they may type.
+const REGIONS: &[(PlatformRoute, &str, &[&str])] = &[
+    (PlatformRoute::BR1, "Brazil", &["br", "brazil"]),
+    (PlatformRoute::EUN1, "EUNE", &["eune", "eun", "eu nordic east", "europe nordic east"]),
+    (PlatformRoute::EUW1, "EUW", &["euw", "eu west", "europe west", "europe"]),
+    (PlatformRoute::JP1, "Japan", &["jp", "japan"]),
+    (PlatformRoute::KR, "South Korea", &["korea", "south korea"]),
+    (PlatformRoute::NA1, "North America", &["na", "north america"]),
+    (PlatformRoute::OC1, "Oceania", &["oce", "oc", "oceania"]),
+    (PlatformRoute::RU, "Russia", &["russia"]),
+    (PlatformRoute::LA1, "Latin America", &["lan", "latin america north"]),
+    (PlatformRoute::LA2, "Latin America", &["las", "latin america south"]),
+    (PlatformRoute::ME1, "Middle East", &["me", "mena", "middle east"]),
+    (PlatformRoute::PH2, "Philippines", &["ph", "philippines"]),
+    (PlatformRoute::SG2, "Singapore", &["sg", "singapore"]),
+    (PlatformRoute::TH2, "Thailand", &["th", "thailand"]),
+    (PlatformRoute::TR1, "Turkey", &["tr", "turkey", "turkiye"]),
+    (PlatformRoute::TW2, "Taiwan", &["tw", "taiwan"]),
+    (PlatformRoute::VN2, "Vietnam", &["vn", "vietnam"]),
+    (PlatformRoute::PBE1, "PBE", &["pbe"]),
+];
+
+/// A region name that matches no region, with the closest codes for the error message.
+#[derive(Debug, Clone)]
+pub struct UnknownRegion {
+    pub input: String,
+    pub suggestions: Vec<PlatformRoute>,
+}
+
+impl fmt::Display for UnknownRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown region `{}`.", self.input)?;
+        if self.suggestions.is_empty() {
+            let codes: Vec<String> = all().map(|region| format!("`{}`", region)).collect();
+            write!(f, " Valid regions: {}.", codes.join(", "))
+        } else {
+            let suggestions: Vec<String> =
+                self.suggestions.iter().map(|region| format!("`{}`", label(*region))).collect();
+            write!(f, " Did you mean {}?", suggestions.join(" or "))
+        }
+    }
+}
+
+impl std::error::Error for UnknownRegion {}
+
+/// The regions in display order.
+pub fn all() -> impl Iterator<Item = PlatformRoute> {
+    REGIONS.iter().map(|(region, _, _)| *region)
+}
+
+/// Parses a region code (`euw1`, `KR`) or a common name (`euw`, `na`, `korea`), ignoring case,
+/// spaces and punctuation.
+pub fn parse(input: &str) -> Result<PlatformRoute, UnknownRegion> {
+    let normalized = utils::normalize(input);
+
+    let found = REGIONS.iter().find(|(region, _, aliases)| {
+        utils::normalize(region.into()) == normalized
+            || aliases.iter().any(|alias| utils::normalize(alias) == normalized)
+    });
+    if let Some((region, _, _)) = found {
+        return Ok(*region);
+    }
+
+    let names = REGIONS
+        .iter()
+        .flat_map(|(region, _, aliases)| std::iter::once(<&str>::from(region)).chain(aliases.iter().copied()));
+    let mut suggestions = Vec::new();
+    for name in utils::closest(input, names, 5) {
+        if let Ok(region) = parse(name) {
+            if !suggestions.contains(&region) {
+                suggestions.push(region);
+            }
+        }
+    }
+    suggestions.truncate(3);
+
+    Err(UnknownRegion { input: input.trim().to_string(), suggestions })
+}
+
+/// The name shown to users, e.g. `EUW (EUW1)`.
+pub fn label(region: PlatformRoute) -> String {
+    match REGIONS.iter().find(|(known, _, _)| *known == region) {
+        Some((_, name, _)) => format!("{} ({})", name, region),
+        None => region.to_string(),
+    }
+}
+
+/// Splits a `--region <code>` or `region:<code>` override off command arguments. Returns the
+/// remaining arguments and the region, if one was given.
+pub fn take_override(input: &str) -> Result<(String, Option<PlatformRoute>), UnknownRegion> {
+    let mut rest = Vec::new();
+    let mut region = None;
+
+    let mut words = input.split_whitespace();
+    while let Some(word) = words.next() {
+        let value = if word.eq_ignore_ascii_case("--region") {
+            words.next().unwrap_or_default()
+        } else if word.get(..7).is_some_and(|head| head.eq_ignore_ascii_case("region:")) {
+            match &word[7..] {
+                "" => words.next().unwrap_or_default(),
+                value => value,
+            }
+        } else {
+            rest.push(word);
+            continue;
+        };
+
+        region = Some(parse(value)?);
+    }
+
+    Ok((rest.join(" "), region))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_codes_and_aliases() {
+        assert_eq!(parse("EUW1").unwrap(), PlatformRoute::EUW1);
+        assert_eq!(parse("kr").unwrap(), PlatformRoute::KR);
+        assert_eq!(parse("euw").unwrap(), PlatformRoute::EUW1);
+        assert_eq!(parse(" South-Korea ").unwrap(), PlatformRoute::KR);
+        assert_eq!(parse("turkiye").unwrap(), PlatformRoute::TR1);
+    }
+
+    #[test]
+    fn suggests_close_regions() {
+        let unknown = parse("koreaa").unwrap_err();
+        assert_eq!(unknown.suggestions.first(), Some(&PlatformRoute::KR));
+        assert!(unknown.to_string().contains("Did you mean `South Korea (KR)`"), "{}", unknown);
+
+        let unknown = parse("atlantis and beyond").unwrap_err();
+        assert!(unknown.suggestions.is_empty());
+        assert!(unknown.to_string().contains("Valid regions: `BR1`"), "{}", unknown);
+    }
+
+    #[test]
+    fn takes_the_region_override_off_the_input() {
+        let (rest, region) = take_override("Hide on bush#KR1 --region kr").unwrap();
+        assert_eq!((rest.as_str(), region), ("Hide on bush#KR1", Some(PlatformRoute::KR)));
+
+        let (rest, region) = take_override("REGION:euw Faker#KR1").unwrap();
+        assert_eq!((rest.as_str(), region), ("Faker#KR1", Some(PlatformRoute::EUW1)));
+
+        let (rest, region) = take_override("Faker#KR1 region: na").unwrap();
+        assert_eq!((rest.as_str(), region), ("Faker#KR1", Some(PlatformRoute::NA1)));
+
+        let (rest, region) = take_override("Faker#KR1 regional").unwrap();
+        assert_eq!((rest.as_str(), region), ("Faker#KR1 regional", None));
+
+        assert_eq!(take_override("Faker#KR1 --region nowhere").unwrap_err().input, "nowhere");
+    }
+}
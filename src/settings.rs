@@ -0,0 +1,200 @@
+use lazy_static::lazy_static;
+use riven::consts::PlatformRoute;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{GuildId, UserId};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::riot::region;
+use crate::utils;
+
+const SETTINGS_PATH: &str = "guild_settings.json";
+
+/// Per-guild configuration, edited through the `config` command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildSettings {
+    pub region: Option<String>,
+    pub prefixes: Vec<String>,
+    pub allowed_channels: Vec<u64>,
+    pub feed_channel: Option<u64>,
+    pub language: String,
+    pub disabled_commands: Vec<String>,
+    pub admin_role: Option<u64>,
+    /// Post the free champion rotation to the feed channel when it changes.
+    pub rotation_announcements: bool,
+    /// Post new and updated incidents and maintenances to the feed channel.
+    pub status_alerts: bool,
+    /// Riot IDs members linked with the `link` command, by Discord user id.
+    pub linked_accounts: HashMap<u64, String>,
+}
+
+impl Default for GuildSettings {
+    fn default() -> Self {
+        GuildSettings {
+            region: None,
+            prefixes: Vec::new(),
+            allowed_channels: Vec::new(),
+            feed_channel: None,
+            language: "en".to_string(),
+            disabled_commands: Vec::new(),
+            admin_role: None,
+            rotation_announcements: false,
+            status_alerts: false,
+            linked_accounts: HashMap::new(),
+        }
+    }
+}
+
+impl GuildSettings {
+    pub fn region(&self) -> Option<PlatformRoute> {
+        self.region.as_deref().and_then(|code| region::parse(code).ok())
+    }
+
+    /// Configured prefixes, falling back to the bot-wide `discord.prefixes` when none are set.
+    pub fn prefixes(&self) -> Vec<&str> {
+        if self.prefixes.is_empty() {
+            crate::config::get().discord.prefixes.iter().map(String::as_str).collect()
+        } else {
+            self.prefixes.iter().map(String::as_str).collect()
+        }
+    }
+
+    pub fn is_command_enabled(&self, name: &str) -> bool {
+        !self.disabled_commands.iter().any(|disabled| disabled == name)
+    }
+
+    /// The Riot ID the member linked, if any.
+    pub fn linked_account(&self, user_id: UserId) -> Option<&str> {
+        self.linked_accounts.get(&user_id.get()).map(String::as_str)
+    }
+
+    pub fn is_channel_allowed(&self, channel_id: u64) -> bool {
+        self.allowed_channels.is_empty() || self.allowed_channels.contains(&channel_id)
+    }
+}
+
+pub struct SettingsStore {
+    path: PathBuf,
+    guilds: Mutex<HashMap<u64, GuildSettings>>,
+}
+
+impl SettingsStore {
+    /// Reads the store at `path`. A file that cannot be parsed is moved aside rather than
+    /// overwritten by the next update, and one that cannot be moved either stops the bot.
+    fn load(path: PathBuf) -> Self {
+        let guilds = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|why| {
+                let aside = utils::set_aside(&path).unwrap_or_else(|error| {
+                    panic!(
+                        "{} cannot be parsed ({}) or moved aside ({}), refusing to overwrite it",
+                        path.display(),
+                        why,
+                        error
+                    )
+                });
+                tracing::error!(
+                    "Error parsing {}, moved it to {} and starting with no settings: {:?}",
+                    path.display(),
+                    aside.display(),
+                    why
+                );
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        SettingsStore { path, guilds: Mutex::new(guilds) }
+    }
+
+    /// Returns the settings for a guild, or the defaults outside of guilds.
+    pub fn get(&self, guild_id: Option<GuildId>) -> GuildSettings {
+        guild_id
+            .and_then(|id| self.guilds.lock().unwrap().get(&id.get()).cloned())
+            .unwrap_or_default()
+    }
+
+    /// Every guild with stored settings, for the background trackers.
+    pub fn all(&self) -> Vec<(GuildId, GuildSettings)> {
+        self.guilds
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, settings)| (GuildId::new(*id), settings.clone()))
+            .collect()
+    }
+
+    /// Applies `change` to the guild's settings and writes the store to disk.
+    pub fn update<F>(&self, guild_id: GuildId, change: F) -> GuildSettings
+    where
+        F: FnOnce(&mut GuildSettings),
+    {
+        let mut guilds = self.guilds.lock().unwrap();
+        let settings = guilds.entry(guild_id.get()).or_default();
+        change(settings);
+        let updated = settings.clone();
+
+        if let Err(why) = self.save(&guilds) {
+            tracing::error!("Error saving {}: {:?}", self.path.display(), why);
+        }
+
+        updated
+    }
+
+    /// Writes the store to disk, e.g. before shutting down.
+    pub fn flush(&self) -> anyhow::Result<()> {
+        let guilds = self.guilds.lock().unwrap();
+        self.save(&guilds)
+    }
+
+    /// Writes to a temporary file first so a crash mid-write never leaves a truncated store behind.
+    fn save(&self, guilds: &HashMap<u64, GuildSettings>) -> anyhow::Result<()> {
+        let contents = serde_json::to_string_pretty(guilds)?;
+        let temporary = self.path.with_extension("json.tmp");
+        fs::write(&temporary, contents)?;
+        fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Tests get a store of their own, so they never touch the bot's settings.
+#[cfg(not(test))]
+fn settings_path() -> PathBuf {
+    PathBuf::from(SETTINGS_PATH)
+}
+
+#[cfg(test)]
+fn settings_path() -> PathBuf {
+    std::env::temp_dir().join(format!("kir-{}-{}", std::process::id(), SETTINGS_PATH))
+}
+
+lazy_static! {
+    pub static ref SETTINGS: SettingsStore = SettingsStore::load(settings_path());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_a_corrupt_store_aside_instead_of_overwriting_it() {
+        let dir = std::env::temp_dir().join(format!("kir-settings-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("guild_settings.json");
+        fs::write(&path, r#"{"1": {"prefixes": ["!"#).unwrap();
+
+        let store = SettingsStore::load(path.clone());
+        assert!(store.all().is_empty());
+        store.update(GuildId::new(1), |settings| settings.prefixes.push("?".to_string()));
+
+        let aside: Vec<PathBuf> =
+            fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).filter(|file| *file != path).collect();
+        assert_eq!(aside.len(), 1);
+        assert!(aside[0].to_string_lossy().contains("guild_settings.json.corrupt-"));
+        assert_eq!(fs::read_to_string(&aside[0]).unwrap(), r#"{"1": {"prefixes": ["!"#);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,14 @@
+use serenity::model::id::GuildId;
+use serenity::utils::shard_id;
+
+use crate::config::{self, ShardingMode};
+
+/// Whether `guild_id` belongs to one of the shards run by this process. Background trackers use this
+/// so that several processes splitting the shards do not post the same update twice.
+pub fn is_local(guild_id: GuildId) -> bool {
+    let sharding = &config::get().sharding;
+    match sharding.mode {
+        ShardingMode::Single | ShardingMode::Auto => true,
+        ShardingMode::Range => (sharding.first..=sharding.last).contains(&shard_id(guild_id, sharding.total)),
+    }
+}
@@ -0,0 +1,95 @@
+use lazy_static::lazy_static;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+
+struct State {
+    requested: watch::Sender<bool>,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+lazy_static! {
+    static ref STATE: State = State {
+        requested: watch::channel(false).0,
+        in_flight: AtomicUsize::new(0),
+        idle: Notify::new(),
+    };
+}
+
+/// Stops new work from starting and wakes everything waiting in [`requested`].
+pub fn request() {
+    STATE.requested.send_replace(true);
+}
+
+pub fn is_requested() -> bool {
+    *STATE.requested.borrow()
+}
+
+/// Resolves once shutdown has been requested, for background loops to `select!` on.
+pub async fn requested() {
+    let mut receiver = STATE.requested.subscribe();
+    let _ = receiver.wait_for(|requested| *requested).await;
+}
+
+/// Marks a command as running until the guard is dropped.
+pub struct InFlight(());
+
+pub fn track() -> InFlight {
+    STATE.in_flight.fetch_add(1, Ordering::SeqCst);
+    InFlight(())
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if STATE.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            STATE.idle.notify_waiters();
+        }
+    }
+}
+
+/// Waits for running commands to finish. Returns how many were still running after `timeout`.
+pub async fn drain(timeout: Duration) -> usize {
+    let wait = async {
+        loop {
+            let idle = STATE.idle.notified();
+            if STATE.in_flight.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            idle.await;
+        }
+    };
+
+    let _ = tokio::time::timeout(timeout, wait).await;
+    STATE.in_flight.load(Ordering::SeqCst)
+}
+
+/// Resolves on SIGINT (Ctrl+C) or, on Unix, SIGTERM.
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(why) = tokio::signal::ctrl_c().await {
+            tracing::error!("Error listening for Ctrl+C: {:?}", why);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(why) => {
+                tracing::error!("Error listening for SIGTERM: {:?}", why);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
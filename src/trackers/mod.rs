@@ -0,0 +1,72 @@
+//! Background jobs that watch Riot for changes and post them to the guilds' feed channels. Each
+//! process only posts to guilds on its own shards, see [`crate::sharding::is_local`].
+
+use riven::consts::PlatformRoute;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serenity::all::{ChannelId, CreateMessage, GuildId, Http};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::commands::region::RegionCommand;
+use crate::metrics;
+use crate::settings::{GuildSettings, SETTINGS};
+use crate::sharding;
+use crate::utils;
+
+pub mod rotation;
+pub mod status;
+
+/// Starts every tracker. They stop on shutdown.
+pub fn spawn(http: Arc<Http>) {
+    tokio::spawn(rotation::run(http.clone()));
+    tokio::spawn(status::run(http));
+}
+
+/// The local guilds with a feed channel that `subscribed` selects, grouped by their region.
+fn feeds(subscribed: impl Fn(&GuildSettings) -> bool) -> HashMap<PlatformRoute, Vec<(GuildId, ChannelId)>> {
+    let mut feeds: HashMap<PlatformRoute, Vec<(GuildId, ChannelId)>> = HashMap::new();
+    for (guild_id, settings) in SETTINGS.all() {
+        let Some(channel_id) = settings.feed_channel else { continue };
+        if !subscribed(&settings) || !sharding::is_local(guild_id) {
+            continue;
+        }
+        let platform = RegionCommand::get_region(Some(guild_id));
+        feeds.entry(platform).or_default().push((guild_id, ChannelId::new(channel_id)));
+    }
+    feeds
+}
+
+/// Posts `message` to a guild's feed channel, logging failures such as a deleted channel or missing
+/// permissions.
+async fn post(http: &Http, guild_id: GuildId, channel_id: ChannelId, message: CreateMessage) {
+    if let Err(why) = channel_id.send_message(http, message).await {
+        metrics::record_error("discord");
+        tracing::warn!(guild = guild_id.get(), channel = channel_id.get(), "Error posting to the feed channel: {:?}", why);
+    }
+}
+
+/// Reads what a tracker saw before the last restart, or the default if nothing was saved. A file
+/// that cannot be parsed is moved aside before the tracker saves over it.
+fn load_state<T: DeserializeOwned + Default>(path: &str) -> T {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|why| {
+            match utils::set_aside(Path::new(path)) {
+                Ok(aside) => tracing::error!("Error parsing {}, moved it to {}: {:?}", path, aside.display(), why),
+                Err(error) => tracing::error!("Error parsing {} and moving it aside: {:?}, {:?}", path, why, error),
+            }
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/// Writes to a temporary file first so a crash mid-write never leaves a truncated file behind.
+fn save_state<T: Serialize>(path: &str, state: &T) -> anyhow::Result<()> {
+    let temporary = Path::new(path).with_extension("json.tmp");
+    fs::write(&temporary, serde_json::to_string_pretty(state)?)?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! Announces a new free champion rotation in the feed channel of guilds that turned it on.
+
+use anyhow::Result;
+use riven::consts::{Champion, PlatformRoute};
+use riven::models::champion_v3::ChampionInfo;
+use serde::{Deserialize, Serialize};
+use serenity::all::{CreateEmbed, CreateMessage, Http};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::commands::rotation::champion_list;
+use crate::riot::{self, region};
+use crate::{ddragon, metrics, shutdown};
+
+/// The rotation changes weekly, but not at the same hour everywhere.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The last rotation seen per region, so restarts do not announce it again.
+const STATE_PATH: &str = "rotation_state.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct StoredRotation {
+    /// Sorted champion ids.
+    free: Vec<i16>,
+}
+
+impl From<&ChampionInfo> for StoredRotation {
+    fn from(info: &ChampionInfo) -> Self {
+        let mut free: Vec<i16> = info.free_champion_ids.iter().map(|champion| champion.0).collect();
+        free.sort_unstable();
+        StoredRotation { free }
+    }
+}
+
+pub async fn run(http: Arc<Http>) {
+    let mut stored: HashMap<String, StoredRotation> = super::load_state(STATE_PATH);
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown::requested() => return,
+        }
+
+        for (platform, feeds) in super::feeds(|settings| settings.rotation_announcements) {
+            let info = match fetch(platform).await {
+                Ok(info) => info,
+                Err(why) => {
+                    metrics::record_error("rotation");
+                    tracing::warn!(region = %platform, "Error fetching the free rotation: {:#}", why);
+                    continue;
+                }
+            };
+
+            let current = StoredRotation::from(&info);
+            let previous = stored.insert(platform.to_string(), current.clone());
+            if previous.as_ref() == Some(&current) {
+                continue;
+            }
+            if let Err(why) = super::save_state(STATE_PATH, &stored) {
+                tracing::error!("Error saving {}: {:?}", STATE_PATH, why);
+            }
+            // The first rotation seen for a region is only stored, there is nothing to compare it to
+            let Some(previous) = previous else { continue };
+
+            tracing::info!(region = %platform, guilds = feeds.len(), "Announcing a new free rotation");
+            // Load Data Dragon for the champion names, falling back to riven's if it is unavailable
+            let _ = ddragon::get().await;
+            let embed = announcement(platform, &info, &previous);
+            for (guild_id, channel_id) in feeds {
+                super::post(&http, guild_id, channel_id, CreateMessage::new().embed(embed.clone())).await;
+            }
+        }
+    }
+}
+
+async fn fetch(platform: PlatformRoute) -> Result<ChampionInfo> {
+    let riot_api = riot::api()?;
+    riot::background(platform, "champion-v3.getChampionInfo", riot_api.champion_v3().get_champion_info(platform)).await
+}
+
+fn announcement(platform: PlatformRoute, info: &ChampionInfo, previous: &StoredRotation) -> CreateEmbed {
+    let added: Vec<Champion> =
+        info.free_champion_ids.iter().copied().filter(|champion| !previous.free.contains(&champion.0)).collect();
+    let removed: Vec<Champion> = previous
+        .free
+        .iter()
+        .filter(|id| !info.free_champion_ids.iter().any(|champion| champion.0 == **id))
+        .map(|id| Champion(*id))
+        .collect();
+
+    let mut embed = CreateEmbed::new()
+        .title("New free champion rotation")
+        .description(region::label(platform))
+        .field(format!("Free this week ({})", info.free_champion_ids.len()), champion_list(&info.free_champion_ids), false);
+    if !added.is_empty() {
+        embed = embed.field("New this week", champion_list(&added), false);
+    }
+    if !removed.is_empty() {
+        embed = embed.field("No longer free", champion_list(&removed), false);
+    }
+    embed
+}
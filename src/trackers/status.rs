@@ -0,0 +1,90 @@
+//! Posts new and updated incidents and maintenances to the feed channel of guilds that turned it on.
+
+use anyhow::Result;
+use riven::consts::PlatformRoute;
+use riven::models::lol_status_v4::{PlatformData, Status};
+use serenity::all::{CreateEmbed, CreateMessage, Http};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::commands::status::{describe, latest_update};
+use crate::riot::{self, region};
+use crate::settings::SETTINGS;
+use crate::{metrics, shutdown};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The last version of each open incident seen per region, so restarts do not post them again.
+const STATE_PATH: &str = "status_state.json";
+
+/// Region → status id → the newest update id, which changes whenever Riot posts an update.
+type Seen = HashMap<String, HashMap<i32, i32>>;
+
+pub async fn run(http: Arc<Http>) {
+    let mut seen: Seen = super::load_state(STATE_PATH);
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown::requested() => return,
+        }
+
+        for (platform, feeds) in super::feeds(|settings| settings.status_alerts) {
+            let data = match fetch(platform).await {
+                Ok(data) => data,
+                Err(why) => {
+                    metrics::record_error("status");
+                    tracing::warn!(region = %platform, "Error fetching the server status: {:#}", why);
+                    continue;
+                }
+            };
+
+            let current: HashMap<i32, i32> = entries(&data)
+                .map(|status| (status.id, latest_update(status).map(|update| update.id).unwrap_or_default()))
+                .collect();
+            let previous = seen.insert(platform.to_string(), current.clone());
+            if previous.as_ref() == Some(&current) {
+                continue;
+            }
+            if let Err(why) = super::save_state(STATE_PATH, &seen) {
+                tracing::error!("Error saving {}: {:?}", STATE_PATH, why);
+            }
+            // Incidents that were open before the first check are only stored
+            let Some(previous) = previous else { continue };
+
+            let changed: Vec<(&Status, bool)> = entries(&data)
+                .filter(|status| previous.get(&status.id) != current.get(&status.id))
+                .map(|status| (status, previous.contains_key(&status.id)))
+                .collect();
+            if changed.is_empty() {
+                continue;
+            }
+
+            tracing::info!(region = %platform, changed = changed.len(), guilds = feeds.len(), "Posting status updates");
+            for (guild_id, channel_id) in feeds {
+                let language = SETTINGS.get(Some(guild_id)).language;
+                let embeds = changed.iter().map(|(status, updated)| alert(platform, status, *updated, &language));
+                // Discord allows 10 embeds per message
+                let message = CreateMessage::new().embeds(embeds.take(10).collect());
+                super::post(&http, guild_id, channel_id, message).await;
+            }
+        }
+    }
+}
+
+async fn fetch(platform: PlatformRoute) -> Result<PlatformData> {
+    let riot_api = riot::api()?;
+    riot::background(platform, "lol-status-v4.getPlatformData", riot_api.lol_status_v4().get_platform_data(platform)).await
+}
+
+fn entries(data: &PlatformData) -> impl Iterator<Item = &Status> {
+    data.maintenances.iter().chain(&data.incidents)
+}
+
+fn alert(platform: PlatformRoute, status: &Status, updated: bool, language: &str) -> CreateEmbed {
+    let kind = if status.maintenance_status.is_some() { "Maintenance" } else { "Incident" };
+    let title = if updated { format!("{} updated", kind) } else { format!("New {}", kind.to_lowercase()) };
+    let (name, value) = describe(status, language);
+    CreateEmbed::new().title(title).description(region::label(platform)).field(name, value, false)
+}